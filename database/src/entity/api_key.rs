@@ -0,0 +1,105 @@
+use anyhow::Result;
+use sea_orm::entity::prelude::*;
+use sea_orm::{IntoActiveModel, QueryOrder, Set};
+use serde::Serialize;
+
+use encode::sha256_encode;
+
+use crate::app_data;
+
+use super::User;
+
+/// 最后使用时间的更新间隔（秒），避免每次请求都写入数据库
+const LAST_USED_INTERVAL: i64 = 5 * 60;
+
+/// API key，用于脚本等长期访问，仅保存 key 的 hash
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "api_key")]
+pub struct Model {
+    /// key id
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// 所属用户 id，使用 key 访问时拥有该用户的权限
+    pub user_id: u32,
+    /// key 名称
+    pub name: String,
+    /// key hash
+    #[serde(skip_serializing)]
+    pub key: String,
+    /// key 前几位，用于区分不同的 key
+    pub prefix: String,
+    /// 创建时间
+    pub created_at: DateTimeUtc,
+    /// 最后使用时间
+    #[sea_orm(nullable)]
+    pub last_used_at: Option<DateTimeUtc>,
+}
+
+impl Model {
+    pub async fn find_by_user(user_id: u32) -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .order_by_asc(Column::Id)
+            .all(app_data().await)
+            .await?)
+    }
+
+    /// 查找 key 对应的用户，同时记录使用时间（间隔 [`LAST_USED_INTERVAL`] 以上才更新）
+    pub async fn find_user(key: &str) -> Result<Option<User>> {
+        let api_key = Entity::find()
+            .filter(Column::Key.eq(sha256_encode(key)))
+            .one(app_data().await)
+            .await?;
+        let Some(api_key) = api_key else {
+            return Ok(None);
+        };
+        let user_id = api_key.user_id;
+        let now = chrono::Utc::now();
+        if api_key
+            .last_used_at
+            .map_or(true, |it| (now - it).num_seconds() >= LAST_USED_INTERVAL)
+        {
+            let mut model = api_key.into_active_model();
+            model.last_used_at = Set(Some(now));
+            model.update(app_data().await).await?;
+        }
+        User::find_by_id(user_id).await
+    }
+
+    pub async fn add(user_id: u32, name: String, key: &str) -> Result<()> {
+        let model = ActiveModel {
+            user_id: Set(user_id),
+            name: Set(name),
+            key: Set(sha256_encode(key)),
+            prefix: Set(key.chars().take(8).collect()),
+            created_at: Set(chrono::Utc::now()),
+            last_used_at: Set(None),
+            ..Default::default()
+        };
+        model.insert(app_data().await).await?;
+        Ok(())
+    }
+
+    /// 删除用户自己的 key
+    pub async fn delete_by_id(id: u32, user_id: u32) -> Result<()> {
+        Entity::delete_many()
+            .filter(Column::Id.eq(id))
+            .filter(Column::UserId.eq(user_id))
+            .exec(app_data().await)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_user(user_id: u32) -> Result<()> {
+        Entity::delete_many()
+            .filter(Column::UserId.eq(user_id))
+            .exec(app_data().await)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use sea_orm::entity::prelude::*;
+use sea_orm::{QueryOrder, QuerySelect, QueryTrait, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data;
+
+#[derive(Deserialize)]
+pub struct SearchParam {
+    username: Option<String>,
+    action: Option<String>,
+    /// 仅返回 id 小于此值的记录，用于分页
+    before_id: Option<u32>,
+    /// 返回条数，默认 100
+    limit: Option<u64>,
+}
+
+/// 审计日志，记录登录及修改操作
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "audit_log")]
+pub struct Model {
+    /// 日志 id
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// 操作时间
+    pub created_at: DateTimeUtc,
+    /// 操作用户名，登录失败时为尝试的账号
+    pub username: String,
+    /// 客户端 IP
+    pub ip: String,
+    /// 操作类型，例如 login、downloader.modify
+    pub action: String,
+    /// 操作对象
+    pub target: String,
+    /// 是否成功
+    pub success: bool,
+}
+
+impl Model {
+    pub async fn find_by_param(param: SearchParam) -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .apply_if(param.username, |it, v| it.filter(Column::Username.eq(v)))
+            .apply_if(param.action, |it, v| {
+                it.filter(Column::Action.like(format!("{v}%")))
+            })
+            .apply_if(param.before_id, |it, v| it.filter(Column::Id.lt(v)))
+            .order_by_desc(Column::Id)
+            .limit(param.limit.unwrap_or(100))
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn add(
+        username: &str,
+        ip: &str,
+        action: &str,
+        target: &str,
+        success: bool,
+    ) -> Result<()> {
+        let model = ActiveModel {
+            created_at: Set(chrono::Utc::now()),
+            username: Set(username.to_owned()),
+            ip: Set(ip.to_owned()),
+            action: Set(action.to_owned()),
+            target: Set(target.to_owned()),
+            success: Set(success),
+            ..Default::default()
+        };
+        model.insert(app_data().await).await?;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
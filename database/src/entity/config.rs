@@ -1,8 +1,12 @@
-use anyhow::Result;
+use std::ops::Range;
+
+use anyhow::{Context, Result};
 use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::OnConflict;
 use sea_orm::Set;
+use uuid::Uuid;
 
-use encode::sha256_encode;
+use eventbus::{Event, EventBus};
 
 use crate::app_data;
 
@@ -16,23 +20,67 @@ impl Config {
             .unwrap_or(false)
     }
 
-    pub async fn username(&self) -> String {
-        get_by_key("username").await.unwrap_or("admin".into())
+    /// 登录会话有效期（小时）
+    pub async fn session_expire_hours(&self) -> u32 {
+        get_by_key("session_expire_hours")
+            .await
+            .and_then(|it| it.parse().ok())
+            .unwrap_or(7 * 24)
     }
 
-    pub async fn password(&self) -> String {
-        // 默认密码为 admin123
-        let default_password = "240be518fabd2724ddb6f04eeb1da5967448d7e831c08c8fa822809f74c720a9";
-        get_by_key("password")
+    /// torznab 接口的 apikey，不存在时自动生成
+    ///
+    /// 并发请求可能同时生成，仅在不存在时写入，之后统一读取已保存的 apikey
+    pub async fn torznab_apikey(&self) -> Result<String> {
+        if let Some(key) = get_by_key("torznab_apikey").await {
+            return Ok(key);
+        }
+        let model = ActiveModel {
+            key: Set("torznab_apikey".into()),
+            value: Set(Uuid::new_v4().simple().to_string()),
+        };
+        let on_conflict = OnConflict::column(Column::Key).do_nothing().to_owned();
+        Entity::insert(model)
+            .on_conflict(on_conflict)
+            .exec_without_returning(app_data().await)
+            .await?;
+        get_by_key("torznab_apikey")
             .await
-            .unwrap_or(default_password.into())
+            .context("torznab apikey not found")
     }
 
+    /// 内网访问是否跳过鉴权
     pub async fn auth_intranet(&self) -> bool {
         get_by_key("auth_intranet")
             .await
             .and_then(|it| it.parse().ok())
-            .unwrap_or(true)
+            .unwrap_or(false)
+    }
+
+    /// 跳过鉴权的网段（CIDR），为空时使用私有地址网段
+    pub async fn intranet_cidrs(&self) -> Vec<String> {
+        get_list_by_key("intranet_cidrs").await
+    }
+
+    /// 可信的反向代理地址（IP 或 CIDR），仅信任来自这些地址的 X-Forwarded-For
+    pub async fn trusted_proxies(&self) -> Vec<String> {
+        get_list_by_key("trusted_proxies").await
+    }
+
+    /// 内置下载器 BitTorrent 监听端口范围
+    pub async fn embedded_listen_ports(&self) -> Range<u16> {
+        get_by_key("embedded_listen_ports")
+            .await
+            .and_then(|it| parse_port_range(&it))
+            .unwrap_or(4240..4260)
+    }
+
+    /// 内置下载器新添加的 torrent 是否按顺序下载
+    pub async fn embedded_sequential(&self) -> bool {
+        get_by_key("embedded_sequential")
+            .await
+            .and_then(|it| it.parse().ok())
+            .unwrap_or(false)
     }
 }
 
@@ -41,17 +89,40 @@ impl Config {
         save_config("bangumi_default_status", val).await
     }
 
-    pub async fn set_username(&self, val: Option<String>) -> Result<()> {
-        save_config("username", val).await
+    pub async fn set_session_expire_hours(&self, val: Option<u32>) -> Result<()> {
+        save_config("session_expire_hours", val).await
     }
 
-    pub async fn set_password<D: AsRef<[u8]>>(&self, val: Option<D>) -> Result<()> {
-        save_config("password", val.map(sha256_encode)).await
+    /// 重新生成 torznab apikey，原有 apikey 立即失效
+    pub async fn regenerate_torznab_apikey(&self) -> Result<String> {
+        let key = Uuid::new_v4().simple().to_string();
+        save_config("torznab_apikey", Some(&key)).await?;
+        Ok(key)
     }
 
     pub async fn set_auth_intranet(&self, val: Option<bool>) -> Result<()> {
         save_config("auth_intranet", val).await
     }
+
+    pub async fn set_intranet_cidrs(&self, val: Option<Vec<String>>) -> Result<()> {
+        save_config("intranet_cidrs", val.map(|it| it.join(","))).await
+    }
+
+    pub async fn set_trusted_proxies(&self, val: Option<Vec<String>>) -> Result<()> {
+        save_config("trusted_proxies", val.map(|it| it.join(","))).await
+    }
+
+    /// 端口范围格式为 `起始端口-结束端口`，不包含结束端口
+    pub async fn set_embedded_listen_ports(&self, val: Option<String>) -> Result<()> {
+        if let Some(val) = &val {
+            parse_port_range(val).with_context(|| format!("invalid port range `{val}`"))?;
+        }
+        save_config("embedded_listen_ports", val).await
+    }
+
+    pub async fn set_embedded_sequential(&self, val: Option<bool>) -> Result<()> {
+        save_config("embedded_sequential", val).await
+    }
 }
 
 async fn get_by_key(key: &str) -> Option<String> {
@@ -62,18 +133,37 @@ async fn get_by_key(key: &str) -> Option<String> {
         .map(|it| it.value)
 }
 
+/// 逗号分隔的列表配置
+async fn get_list_by_key(key: &str) -> Vec<String> {
+    let value = get_by_key(key).await.unwrap_or_default();
+    let list = value.split(',').map(str::trim).filter(|it| !it.is_empty());
+    list.map(str::to_owned).collect()
+}
+
+/// 解析 `起始端口-结束端口` 格式的端口范围
+fn parse_port_range(value: &str) -> Option<Range<u16>> {
+    let (start, end) = value.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let end = end.trim().parse().ok()?;
+    Some(start..end).filter(|it| !it.is_empty())
+}
+
+/// 保存配置，值有变化时发布设置修改事件
 async fn save_config<T: ToString>(key: &str, val: Option<T>) -> Result<()> {
     if let Some(val) = val {
+        let value = val.to_string();
         let model = ActiveModel {
             key: Set(key.into()),
-            value: Set(val.to_string()),
+            value: Set(value.clone()),
         };
         let txn = app_data().await;
         let exist = Entity::find_by_id(key).one(txn).await?;
         match exist {
+            Some(it) if it.value == value => return Ok(()),
             None => drop(Entity::insert(model).exec(txn).await?),
             Some(_) => drop(Entity::update(model).exec(txn).await?),
         }
+        EventBus.publish(Event::SettingChanged { key: key.into() });
     }
     Ok(())
 }
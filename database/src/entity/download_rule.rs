@@ -0,0 +1,103 @@
+use anyhow::Result;
+use sea_orm::entity::prelude::*;
+use sea_orm::{IntoActiveModel, NotSet, QueryOrder, QueryTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data;
+
+#[derive(Deserialize)]
+pub struct SearchParam {
+    name: Option<String>,
+    downloader_id: Option<u32>,
+}
+
+/// 下载路由规则，按优先级从高到低匹配，条件为空时匹配任意值
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "download_rule")]
+pub struct Model {
+    /// 规则 id
+    #[sea_orm(primary_key)]
+    #[serde(default)]
+    pub id: u32,
+    /// 规则名称
+    pub name: String,
+    /// 优先级，数值越大越先匹配
+    #[serde(default)]
+    pub priority: i32,
+    /// 目标下载器 id
+    pub downloader_id: u32,
+    /// 下载基础路径，为空时使用下载器的下载路径
+    #[serde(default)]
+    pub download_dir: String,
+    /// 匹配的索引器 id
+    #[sea_orm(nullable)]
+    pub indexer_id: Option<u32>,
+    /// 匹配的订阅（tmdb id）
+    #[sea_orm(nullable)]
+    pub tmdb_id: Option<i64>,
+    /// 匹配电影或剧集
+    #[sea_orm(nullable)]
+    pub is_movie: Option<bool>,
+    /// 匹配的发布组（忽略大小写）
+    #[sea_orm(nullable)]
+    pub release_group: Option<String>,
+    /// 是否启用
+    pub enable: bool,
+}
+
+impl Model {
+    /// 按优先级从高到低获取所有启用的规则
+    pub async fn find_all_enable() -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .filter(Column::Enable.eq(true))
+            .order_by_desc(Column::Priority)
+            .order_by_asc(Column::Id)
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn find_by_param(param: SearchParam) -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .apply_if(param.name, |it, v| {
+                it.filter(Column::Name.like(format!("%{v}%")))
+            })
+            .apply_if(param.downloader_id, |it, v| {
+                it.filter(Column::DownloaderId.eq(v))
+            })
+            .order_by_desc(Column::Priority)
+            .order_by_asc(Column::Id)
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn add(self) -> Result<()> {
+        let mut model = self.into_active_model().reset_all();
+        model.id = NotSet;
+        model.insert(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn modify(self) -> Result<()> {
+        let model = self.into_active_model().reset_all();
+        model.update(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_id(id: u32) -> Result<()> {
+        Entity::delete_by_id(id).exec(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_downloader(downloader_id: u32) -> Result<()> {
+        Entity::delete_many()
+            .filter(Column::DownloaderId.eq(downloader_id))
+            .exec(app_data().await)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
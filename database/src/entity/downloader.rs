@@ -1,20 +1,41 @@
+use anyhow::Result;
 use sea_orm::entity::prelude::*;
+use sea_orm::{IntoActiveModel, NotSet, QueryTrait};
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+use crate::app_data;
+
+use super::{DownloadRule, PathMapping};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "i32", db_type = "Integer")]
 pub enum Category {
+    #[serde(rename = "aira2")]
     #[sea_orm(num_value = 0)]
     Aira2,
+    #[serde(rename = "qbittorrent")]
     #[sea_orm(num_value = 1)]
     Qbittorrent,
+    #[serde(rename = "transmission")]
     #[sea_orm(num_value = 2)]
     Transmission,
+    /// 内置下载器，不需要连接外部下载器
+    #[serde(rename = "embedded")]
+    #[sea_orm(num_value = 3)]
+    Embedded,
+}
+
+#[derive(Deserialize)]
+pub struct SearchParam {
+    name: Option<String>,
+    cat: Option<Category>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Deserialize, Serialize)]
 #[sea_orm(table_name = "downloader")]
 pub struct Model {
     #[sea_orm(primary_key)]
+    #[serde(default)]
     pub id: u32,
     /// 下载器类型
     pub cat: Category,
@@ -25,11 +46,67 @@ pub struct Model {
     /// 下载器登录用户名
     #[sea_orm(nullable)]
     pub username: Option<String>,
-    /// 下载器通行密钥
+    /// 下载器通行密钥，不对外返回，修改时为空则保留原有值
     #[sea_orm(nullable)]
+    #[serde(skip_serializing)]
     pub password: Option<String>,
     /// 下载器下载路径
     pub download_dir: String,
+    /// 下载分类，用于区分 mikanarr 添加的 torrent，为空时不区分
+    /// - qbittorrent 使用分类
+    /// - transmission 使用标签
+    /// - aira2 使用下载路径下的同名子目录
+    #[serde(default = "Model::default_category")]
+    pub category: String,
+}
+
+impl Model {
+    fn default_category() -> String {
+        "mikanarr".into()
+    }
+
+    pub async fn find_all() -> Result<Vec<Self>> {
+        Ok(Entity::find().all(app_data().await).await?)
+    }
+
+    pub async fn find_by_param(param: SearchParam) -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .apply_if(param.name, |it, v| {
+                it.filter(Column::Name.like(format!("%{v}%")))
+            })
+            .apply_if(param.cat, |it, v| it.filter(Column::Cat.eq(v)))
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn find_by_id(id: u32) -> Result<Option<Self>> {
+        Ok(Entity::find_by_id(id).one(app_data().await).await?)
+    }
+
+    pub async fn add(self) -> Result<()> {
+        let mut model = self.into_active_model().reset_all();
+        model.id = NotSet;
+        model.insert(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn modify(self) -> Result<()> {
+        let keep_password = self.password.is_none();
+        let mut model = self.into_active_model().reset_all();
+        if keep_password {
+            model.password = NotSet;
+        }
+        model.update(app_data().await).await?;
+        Ok(())
+    }
+
+    /// 删除下载器，同时删除引用此下载器的路径映射及路由规则
+    pub async fn delete_by_id(id: u32) -> Result<()> {
+        PathMapping::delete_by_downloader(id).await?;
+        DownloadRule::delete_by_downloader(id).await?;
+        Entity::delete_by_id(id).exec(app_data().await).await?;
+        Ok(())
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -16,7 +16,7 @@ pub enum Category {
     Torznab,
 }
 
-#[derive(Deserialize)]
+#[derive(Default, Deserialize)]
 pub struct SearchParam {
     name: Option<String>,
     category: Option<Category>,
@@ -58,6 +58,10 @@ impl Model {
             .await?)
     }
 
+    pub async fn find_by_id(id: u32) -> Result<Option<Self>> {
+        Ok(Entity::find_by_id(id).one(app_data().await).await?)
+    }
+
     pub async fn add(self) -> Result<()> {
         let mut model = self.into_active_model().reset_all();
         model.id = NotSet;
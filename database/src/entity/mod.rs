@@ -1,11 +1,29 @@
+pub use api_key::Model as ApiKey;
+pub use audit_log::{Model as AuditLog, SearchParam as AuditLogSearch};
 pub use config::Config;
-pub use downloader::{Category as DownloaderType, Model as Downloader};
+pub use download_rule::{Model as DownloadRule, SearchParam as DownloadRuleSearch};
+pub use downloader::{
+    Category as DownloaderType, Model as Downloader, SearchParam as DownloaderSearch,
+};
 pub use indexer::{Category as IndexerCategory, Model as Indexer, SearchParam as IndexerSearch};
 pub use mikan_tmdb::Model as MikanTmdb;
+pub use notification::{
+    Channel as NotificationChannel, Model as Notification, SearchParam as NotificationSearch,
+};
+pub use path_mapping::{Model as PathMapping, SearchParam as PathMappingSearch};
+pub use session::Model as Session;
 pub use torrent::{Model as Torrent, SearchParam as TorrentSearch};
+pub use user::{Model as User, Role as UserRole};
 
+mod api_key;
+mod audit_log;
 mod config;
+mod download_rule;
 mod downloader;
 mod indexer;
 mod mikan_tmdb;
+mod notification;
+mod path_mapping;
+mod session;
 mod torrent;
+mod user;
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use sea_orm::entity::prelude::*;
+use sea_orm::{IntoActiveModel, NotSet, QueryTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+pub enum Channel {
+    #[serde(rename = "webhook")]
+    #[sea_orm(num_value = 0)]
+    Webhook,
+    #[serde(rename = "telegram")]
+    #[sea_orm(num_value = 1)]
+    Telegram,
+    #[serde(rename = "bark")]
+    #[sea_orm(num_value = 2)]
+    Bark,
+    #[serde(rename = "server_chan")]
+    #[sea_orm(num_value = 3)]
+    ServerChan,
+    #[serde(rename = "email")]
+    #[sea_orm(num_value = 4)]
+    Email,
+    /// 与 Sonarr/Radarr webhook 相同格式的 webhook
+    #[serde(rename = "sonarr")]
+    #[sea_orm(num_value = 5)]
+    Sonarr,
+}
+
+#[derive(Deserialize)]
+pub struct SearchParam {
+    name: Option<String>,
+    channel: Option<Channel>,
+}
+
+/// 消息通知渠道
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "notification")]
+pub struct Model {
+    /// 通知 id
+    #[sea_orm(primary_key)]
+    #[serde(default)]
+    pub id: u32,
+    /// 通知名称
+    pub name: String,
+    /// 通知渠道
+    pub channel: Channel,
+    /// 渠道配置，格式由渠道决定
+    pub config: Json,
+    /// 需要通知的事件名称列表，为空时通知所有事件
+    #[serde(default = "Model::default_events")]
+    pub events: Json,
+    /// 是否启用
+    pub enable: bool,
+}
+
+impl Model {
+    fn default_events() -> Json {
+        Json::Array(Vec::new())
+    }
+
+    pub async fn find_all_enable() -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .filter(Column::Enable.eq(true))
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn find_by_param(param: SearchParam) -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .apply_if(param.name, |it, v| {
+                it.filter(Column::Name.like(format!("%{v}%")))
+            })
+            .apply_if(param.channel, |it, v| it.filter(Column::Channel.eq(v)))
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn find_by_id(id: u32) -> Result<Option<Self>> {
+        Ok(Entity::find_by_id(id).one(app_data().await).await?)
+    }
+
+    pub async fn add(self) -> Result<()> {
+        let mut model = self.into_active_model().reset_all();
+        model.id = NotSet;
+        model.insert(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn modify(self) -> Result<()> {
+        let model = self.into_active_model().reset_all();
+        model.update(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_id(id: u32) -> Result<()> {
+        Entity::delete_by_id(id).exec(app_data().await).await?;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+use sea_orm::entity::prelude::*;
+use sea_orm::{IntoActiveModel, NotSet, QueryTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::app_data;
+
+#[derive(Deserialize)]
+pub struct SearchParam {
+    downloader_id: Option<u32>,
+}
+
+/// 下载器远程路径到本地路径的映射
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "path_mapping")]
+pub struct Model {
+    /// 映射 id
+    #[sea_orm(primary_key)]
+    #[serde(default)]
+    pub id: u32,
+    /// 下载器 id
+    pub downloader_id: u32,
+    /// 下载器中的路径前缀
+    pub remote_path: String,
+    /// 本地（mikanarr 中）的路径前缀
+    pub local_path: String,
+}
+
+impl Model {
+    pub async fn find_by_downloader(downloader_id: u32) -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .filter(Column::DownloaderId.eq(downloader_id))
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn find_by_param(param: SearchParam) -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .apply_if(param.downloader_id, |it, v| {
+                it.filter(Column::DownloaderId.eq(v))
+            })
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn add(self) -> Result<()> {
+        let mut model = self.into_active_model().reset_all();
+        model.id = NotSet;
+        model.insert(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn modify(self) -> Result<()> {
+        let model = self.into_active_model().reset_all();
+        model.update(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_id(id: u32) -> Result<()> {
+        Entity::delete_by_id(id).exec(app_data().await).await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_downloader(downloader_id: u32) -> Result<()> {
+        Entity::delete_many()
+            .filter(Column::DownloaderId.eq(downloader_id))
+            .exec(app_data().await)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
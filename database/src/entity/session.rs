@@ -0,0 +1,77 @@
+use anyhow::Result;
+use sea_orm::entity::prelude::*;
+use sea_orm::Set;
+
+use encode::sha256_encode;
+
+use crate::app_data;
+
+use super::User;
+
+/// 登录会话，仅保存 token 的 hash
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "session")]
+pub struct Model {
+    /// token hash
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub token: String,
+    /// 用户 id
+    pub user_id: u32,
+    /// 过期时间
+    pub expires_at: DateTimeUtc,
+}
+
+impl Model {
+    /// 创建会话，同时清理已过期的会话
+    pub async fn add(token: &str, user_id: u32, expire_hours: u32) -> Result<()> {
+        Self::delete_expired().await?;
+        let expires_at = chrono::Utc::now() + chrono::Duration::hours(expire_hours.into());
+        let model = ActiveModel {
+            token: Set(sha256_encode(token)),
+            user_id: Set(user_id),
+            expires_at: Set(expires_at),
+        };
+        model.insert(app_data().await).await?;
+        Ok(())
+    }
+
+    /// 查找 token 对应的用户，会话不存在或已过期时为空
+    pub async fn find_user(token: &str) -> Result<Option<User>> {
+        let session = Entity::find_by_id(sha256_encode(token))
+            .filter(Column::ExpiresAt.gt(chrono::Utc::now()))
+            .one(app_data().await)
+            .await?;
+        match session {
+            Some(session) => User::find_by_id(session.user_id).await,
+            None => Ok(None),
+        }
+    }
+
+    pub async fn delete_by_token(token: &str) -> Result<()> {
+        Entity::delete_by_id(sha256_encode(token))
+            .exec(app_data().await)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_user(user_id: u32) -> Result<()> {
+        Entity::delete_many()
+            .filter(Column::UserId.eq(user_id))
+            .exec(app_data().await)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_expired() -> Result<()> {
+        Entity::delete_many()
+            .filter(Column::ExpiresAt.lte(chrono::Utc::now()))
+            .exec(app_data().await)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -43,6 +43,9 @@ pub struct Model {
     pub tvdb_id: Option<i64>,
     /// imdb id
     pub imdb_id: String,
+    /// 来源索引器 id
+    #[sea_orm(nullable)]
+    pub indexer_id: Option<u32>,
 }
 
 impl Model {
@@ -0,0 +1,232 @@
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy as LazyLock;
+use sea_orm::entity::prelude::*;
+use sea_orm::{IntoActiveModel, NotSet, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+use uuid::Uuid;
+
+use encode::{password_hash, password_need_rehash, password_verify};
+
+use crate::app_data;
+
+/// 旧版本的默认密码，不再允许使用
+const DEFAULT_PASSWORD: &str = "admin123";
+
+use super::{ApiKey, Session};
+
+/// 用户角色
+#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// 管理员，可以进行所有操作
+    #[sea_orm(num_value = 0)]
+    Admin,
+    /// 只读用户，仅可以查询
+    #[sea_orm(num_value = 1)]
+    ReadOnly,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "user")]
+pub struct Model {
+    /// 用户 id
+    #[sea_orm(primary_key)]
+    #[serde(default)]
+    pub id: u32,
+    /// 用户名
+    #[sea_orm(unique)]
+    pub username: String,
+    /// 密码 hash，不对外返回，修改时为空则保留原有值
+    #[serde(skip_serializing, default)]
+    pub password: String,
+    /// 用户角色
+    pub role: Role,
+    /// 是否需要修改密码，初始密码由他人设置时为 true，修改前无法访问其他接口
+    #[serde(default)]
+    pub must_change_password: bool,
+}
+
+impl Model {
+    pub async fn find_all() -> Result<Vec<Self>> {
+        Ok(Entity::find()
+            .order_by_asc(Column::Id)
+            .all(app_data().await)
+            .await?)
+    }
+
+    pub async fn find_by_id(id: u32) -> Result<Option<Self>> {
+        Ok(Entity::find_by_id(id).one(app_data().await).await?)
+    }
+
+    /// 校验用户名及密码，成功返回用户，旧版本的 SHA-256 hash 会升级为 Argon2id
+    pub async fn verify(username: &str, password: &str) -> Result<Option<Self>> {
+        let user = Entity::find()
+            .filter(Column::Username.eq(username))
+            .one(app_data().await)
+            .await?;
+        // 用户不存在时同样计算一次 hash，避免通过响应时间判断用户是否存在
+        static DUMMY_HASH: LazyLock<String> = LazyLock::new(|| password_hash("mikanarr"));
+        let hash = user.as_ref().map(|it| it.password.clone());
+        let plain = password.to_owned();
+        let verified = spawn_blocking(move || {
+            let hash = hash.as_deref().unwrap_or(DUMMY_HASH.as_str());
+            password_verify(plain, hash)
+        });
+        let verified = verified.await?;
+        let Some(mut user) = user.filter(|_| verified) else {
+            return Ok(None);
+        };
+        if password_need_rehash(&user.password) {
+            let model = ActiveModel {
+                id: Set(user.id),
+                password: Set(hash_password(password).await?),
+                ..Default::default()
+            };
+            user = model.update(app_data().await).await?;
+        }
+        Ok(Some(user))
+    }
+
+    pub async fn add(self) -> Result<()> {
+        if self.password.is_empty() {
+            bail!("password can't be empty");
+        }
+        let mut model = self.into_active_model().reset_all();
+        model.id = NotSet;
+        model.password = Set(hash_password(model.password.as_ref()).await?);
+        model.must_change_password = Set(true);
+        model.insert(app_data().await).await?;
+        Ok(())
+    }
+
+    /// 修改用户，重设密码或修改角色时删除用户已有的会话，API key 使用时按当前角色鉴权
+    pub async fn modify(self) -> Result<()> {
+        let exist = Self::find_by_id(self.id).await?;
+        let exist = exist.context("user not found")?;
+        if exist.role == Role::Admin && self.role != Role::Admin {
+            Self::ensure_other_admin(self.id).await?;
+        }
+        let revoke = !self.password.is_empty() || exist.role != self.role;
+        let id = self.id;
+        let mut model = self.into_active_model().reset_all();
+        // 管理员重设密码后，用户需要在下次登录时修改密码
+        (model.password, model.must_change_password) = match model.password.as_ref().is_empty() {
+            true => (Set(exist.password), Set(exist.must_change_password)),
+            false => (
+                Set(hash_password(model.password.as_ref()).await?),
+                Set(true),
+            ),
+        };
+        model.update(app_data().await).await?;
+        if revoke {
+            Session::delete_by_user(id).await?;
+        }
+        Ok(())
+    }
+
+    /// 修改密码，同时删除用户已有的会话，需要重新登录
+    pub async fn set_password(id: u32, password: &str) -> Result<()> {
+        if password.is_empty() {
+            bail!("password can't be empty");
+        }
+        let model = ActiveModel {
+            id: Set(id),
+            password: Set(hash_password(password).await?),
+            must_change_password: Set(false),
+            ..Default::default()
+        };
+        model.update(app_data().await).await?;
+        Session::delete_by_user(id).await?;
+        Ok(())
+    }
+
+    /// 将仍在使用旧版本默认密码 admin123 的用户改为随机生成的初始密码，
+    /// 服务启动时调用，返回用户名及初始密码，初始密码仅在此时输出一次
+    pub async fn replace_default_password() -> Result<Vec<(String, String)>> {
+        let users = Entity::find()
+            .filter(Column::MustChangePassword.eq(true))
+            .all(app_data().await)
+            .await?;
+        let mut replaced = Vec::new();
+        for user in users {
+            let hash = user.password.clone();
+            let verified = spawn_blocking(move || password_verify(DEFAULT_PASSWORD, &hash));
+            if !verified.await? {
+                continue;
+            }
+            let password = Uuid::new_v4().simple().to_string()[..16].to_owned();
+            let model = ActiveModel {
+                id: Set(user.id),
+                password: Set(hash_password(&password).await?),
+                ..Default::default()
+            };
+            model.update(app_data().await).await?;
+            // 使用默认密码登录后得到的会话同样失效
+            Session::delete_by_user(user.id).await?;
+            replaced.push((user.username, password));
+        }
+        Ok(replaced)
+    }
+
+    /// 第一个管理员
+    pub async fn find_admin() -> Result<Self> {
+        let admin = Entity::find()
+            .filter(Column::Role.eq(Role::Admin))
+            .order_by_asc(Column::Id)
+            .one(app_data().await)
+            .await?;
+        admin.context("admin user not found")
+    }
+
+    /// 重设指定用户的密码，未指定用户时重设第一个管理员的密码
+    pub async fn reset_password(username: Option<&str>, password: &str) -> Result<String> {
+        let user = match username {
+            None => Self::find_admin().await?,
+            Some(username) => Entity::find()
+                .filter(Column::Username.eq(username))
+                .one(app_data().await)
+                .await?
+                .with_context(|| format!("user `{username}` not found"))?,
+        };
+        Self::set_password(user.id, password).await?;
+        Ok(user.username)
+    }
+
+    /// 删除用户，同时删除用户的会话及 API key
+    pub async fn delete_by_id(id: u32) -> Result<()> {
+        let exist = Self::find_by_id(id).await?;
+        if exist.is_some_and(|it| it.role == Role::Admin) {
+            Self::ensure_other_admin(id).await?;
+        }
+        Session::delete_by_user(id).await?;
+        ApiKey::delete_by_user(id).await?;
+        Entity::delete_by_id(id).exec(app_data().await).await?;
+        Ok(())
+    }
+
+    /// 保证至少保留一个管理员
+    async fn ensure_other_admin(id: u32) -> Result<()> {
+        let count = Entity::find()
+            .filter(Column::Role.eq(Role::Admin))
+            .filter(Column::Id.ne(id))
+            .count(app_data().await)
+            .await?;
+        if count == 0 {
+            bail!("at least one admin user is required");
+        }
+        Ok(())
+    }
+}
+
+/// Argon2 计算耗时较长，在阻塞线程中执行，避免阻塞异步任务
+async fn hash_password(password: &str) -> Result<String> {
+    let password = password.to_owned();
+    Ok(spawn_blocking(move || password_hash(password)).await?)
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,115 +1,166 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
-use once_cell::sync::Lazy as LazyLock;
-use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use anyhow::{bail, ensure, Context, Result};
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement,
+};
 use sea_orm_migration::MigratorTrait;
+use serde::Serialize;
 
 use migrate::Migrator;
 
 pub mod entity;
 mod migrate;
 
-/// 是否启用 sqlx 日志
-static USE_SQLX_LOGGING: LazyLock<bool> = LazyLock::new(|| {
-    std::env::var("USE_SQLX_LOGGING")
-        .map(|it| it.eq_ignore_ascii_case("true"))
-        .unwrap_or(false)
-});
+/// 数据库配置
+#[derive(Clone, Debug)]
+pub struct DataConfig {
+    /// 应用数据目录，存放应用数据库等运行时数据
+    pub app_data_dir: PathBuf,
+    /// 资源数据库路径
+    pub res_data_path: PathBuf,
+    /// 是否启用 sqlx 日志
+    pub sqlx_logging: bool,
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        Self {
+            app_data_dir: PathBuf::from("./data"),
+            res_data_path: PathBuf::from("./resources/res.sqlite"),
+            sqlx_logging: false,
+        }
+    }
+}
+
+static CONFIG: OnceLock<DataConfig> = OnceLock::new();
+
+/// 未调用 [load] 时（例如测试）使用默认配置
+fn config() -> &'static DataConfig {
+    CONFIG.get_or_init(Default::default)
+}
+
+/// 应用数据目录
+pub fn app_data_dir() -> &'static Path {
+    config().app_data_dir.as_path()
+}
+
+static RES_DATA: OnceLock<DatabaseConnection> = OnceLock::new();
+static APP_DATA: OnceLock<DatabaseConnection> = OnceLock::new();
+
+fn app_data_path() -> PathBuf {
+    app_data_dir().join("data.sqlite")
+}
 
 /// 资源数据库，预先嵌入的资源数据，只读不要更改
 async fn res_data() -> &'static DatabaseConnection {
-    static DATA: OnceLock<DatabaseConnection> = OnceLock::new();
-
-    if let Some(data) = DATA.get() {
+    if let Some(data) = RES_DATA.get() {
         return data;
     }
+    match connect_res_data().await {
+        Ok(it) => RES_DATA.get_or_init(|| it),
+        Err(e) => panic!("open res database error: {:#?}", e),
+    }
+}
 
-    let res_data_path = "./resources/res.sqlite";
-    log::info!("res database path: `{}`", res_data_path);
-    let res_data_url = format!("sqlite:file:{}?mode=ro", res_data_path);
+async fn connect_res_data() -> Result<DatabaseConnection> {
+    let res_data_path = &config().res_data_path;
+    log::info!("res database path: `{}`", res_data_path.display());
+    ensure!(
+        res_data_path.is_file(),
+        "res database `{}` not found",
+        res_data_path.display()
+    );
+    let res_data_url = format!("sqlite:file:{}?mode=ro", res_data_path.display());
 
     let mut opt = ConnectOptions::new(res_data_url);
-    opt.sqlx_logging(*USE_SQLX_LOGGING);
-    let database = match Database::connect(opt).await {
-        Ok(it) => it,
-        Err(e) => panic!("open res database error: {:#?}", e),
-    };
-
-    DATA.get_or_init(|| database)
+    opt.sqlx_logging(config().sqlx_logging);
+    Ok(Database::connect(opt).await?)
 }
 
 /// 应用数据库，存放程序运行时数据
 async fn app_data() -> &'static DatabaseConnection {
-    static DATA: OnceLock<DatabaseConnection> = OnceLock::new();
-
-    if let Some(data) = DATA.get() {
+    if let Some(data) = APP_DATA.get() {
         return data;
     }
+    match connect_app_data().await {
+        Ok(it) => APP_DATA.get_or_init(|| it),
+        Err(e) => panic!("open app database error: {:#?}", e),
+    }
+}
 
-    let app_data_path = match std::env::var("MK_APP_DATA") {
-        Ok(it) => Path::new(&it).join("data.sqlite"),
-        Err(_) => Path::new("./data").join("data.sqlite"),
-    };
+async fn connect_app_data() -> Result<DatabaseConnection> {
+    let app_data_dir = app_data_dir();
+    std::fs::create_dir_all(app_data_dir)
+        .with_context(|| format!("create app data dir `{}`", app_data_dir.display()))?;
+    let app_data_path = app_data_path();
     log::info!("app database path: `{}`", app_data_path.display());
     let app_data_url = format!("sqlite:file:{}?mode=rwc", app_data_path.display());
 
     let mut opt = ConnectOptions::new(app_data_url);
-    opt.sqlx_logging(*USE_SQLX_LOGGING);
-    let database = match Database::connect(opt).await {
-        Ok(it) => it,
-        Err(e) => panic!("open app database error: {:#?}", e),
-    };
-    if let Err(e) = Migrator::up(&database, None).await {
-        panic!("app database migrate error: {:#?}", e)
-    }
-
-    DATA.get_or_init(|| database)
+    opt.sqlx_logging(config().sqlx_logging);
+    let database = Database::connect(opt).await?;
+    Migrator::up(&database, None)
+        .await
+        .context("app database migrate error")?;
+    Ok(database)
 }
 
-/// 尝试重设密码，遇到错误取消重设
-async fn reset_password() {
-    let app_data_path = match std::env::var("MK_APP_DATA") {
-        Ok(it) => Path::new(&it).join("reset-password.txt"),
-        Err(_) => Path::new("./data").join("reset-password.txt"),
-    };
-
-    // 路径不存在直接返回
-    if !app_data_path.exists() {
-        return;
+/// 加载数据库连接，资源或数据目录不可用时返回错误
+pub async fn load(config: DataConfig) -> Result<()> {
+    if CONFIG.set(config).is_err() {
+        bail!("database config already loaded");
     }
+    let res_data = connect_res_data().await?;
+    RES_DATA.get_or_init(|| res_data);
+    let app_data = connect_app_data().await?;
+    APP_DATA.get_or_init(|| app_data);
+    Ok(())
+}
 
-    let mut file = match File::open(app_data_path) {
-        Ok(it) => BufReader::new(it),
-        Err(e) => {
-            log::warn!("open reset-password.txt fail: {}", e);
-            return;
-        }
-    };
-
-    let mut password = String::new();
-    if let Err(e) = file.read_line(&mut password) {
-        log::warn!("read reset-password.txt fail: {}", e);
-        return;
-    }
+/// 备份应用数据库到指定文件，文件需要不存在
+pub async fn backup(path: &Path) -> Result<()> {
+    ensure!(
+        !path.exists(),
+        "backup file `{}` already exists",
+        path.display()
+    );
+    let path = path.to_string_lossy().to_string();
+    let stmt = Statement::from_sql_and_values(DbBackend::Sqlite, "VACUUM INTO ?", [path.into()]);
+    app_data().await.execute(stmt).await?;
+    Ok(())
+}
 
-    let password = password.trim();
-    if password.is_empty() {
-        log::warn!("reset-password is empty");
-        return;
-    }
+/// 整理应用数据库，回收删除数据后的空间
+pub async fn vacuum() -> Result<()> {
+    app_data().await.execute_unprepared("VACUUM").await?;
+    Ok(())
+}
 
-    match entity::Config.set_password(Some(password)).await {
-        Ok(_) => log::info!("reset `{}` password: `{}`", "admin", password),
-        Err(e) => log::warn!("save new password error: {}", e),
-    }
+/// 数据库状态
+#[derive(Serialize, Debug)]
+pub struct DataStatus {
+    /// 应用数据库文件大小（字节）
+    pub app_data_size: u64,
+    /// 资源数据库文件大小（字节）
+    pub res_data_size: u64,
+    /// 最后一次执行的迁移
+    pub migration: Option<String>,
+    /// 未执行的迁移数量
+    pub pending_migrations: usize,
 }
 
-/// 加载数据库连接
-pub async fn load() {
-    let _ = res_data().await;
-    let _ = app_data().await;
-    reset_password().await;
+/// 查询数据库状态，同时用于检查应用数据库是否可用
+pub async fn status() -> Result<DataStatus> {
+    let app_data = app_data().await;
+    let applied = Migrator::get_applied_migrations(app_data).await?;
+    let pending = Migrator::get_pending_migrations(app_data).await?;
+    let file_size = |path: &Path| std::fs::metadata(path).map_or(0, |it| it.len());
+    Ok(DataStatus {
+        app_data_size: file_size(&app_data_path()),
+        res_data_size: file_size(&config().res_data_path),
+        migration: applied.last().map(|it| it.name().to_string()),
+        pending_migrations: pending.len(),
+    })
 }
@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+use super::*;
+
+#[derive(DeriveMigrationName)]
+pub(super) struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(downloader_category()).await?;
+        Ok(())
+    }
+}
+
+fn downloader_category() -> TableAlterStatement {
+    alter_table("downloader")
+        .add_column(column("category").string().not_null().default("mikanarr"))
+        .to_owned()
+}
@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+use super::*;
+
+#[derive(DeriveMigrationName)]
+pub(super) struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(download_rule()).await?;
+        manager.alter_table(torrent_indexer()).await?;
+        Ok(())
+    }
+}
+
+fn download_rule() -> TableCreateStatement {
+    create_table("download_rule")
+        .if_not_exists()
+        .col(id().primary_key())
+        .col(column("name").string().not_null())
+        .col(column("priority").integer().not_null().default(0))
+        .col(column("downloader_id").unsigned().not_null())
+        .col(column("download_dir").string().not_null().default(""))
+        .col(column("indexer_id").unsigned().null())
+        .col(column("tmdb_id").big_integer().null())
+        .col(column("is_movie").boolean().null())
+        .col(column("release_group").string().null())
+        .col(column("enable").boolean().not_null().default(true))
+        .to_owned()
+}
+
+fn torrent_indexer() -> TableAlterStatement {
+    alter_table("torrent")
+        .add_column(column("indexer_id").unsigned().null())
+        .to_owned()
+}
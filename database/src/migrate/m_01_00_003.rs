@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+use super::*;
+
+#[derive(DeriveMigrationName)]
+pub(super) struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(path_mapping()).await?;
+        Ok(())
+    }
+}
+
+fn path_mapping() -> TableCreateStatement {
+    create_table("path_mapping")
+        .if_not_exists()
+        .col(id().primary_key())
+        .col(column("downloader_id").unsigned().not_null())
+        .col(column("remote_path").string().not_null())
+        .col(column("local_path").string().not_null())
+        .to_owned()
+}
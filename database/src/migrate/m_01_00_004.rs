@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+use super::*;
+
+#[derive(DeriveMigrationName)]
+pub(super) struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(user()).await?;
+        manager.create_table(session()).await?;
+        manager.create_table(api_key()).await?;
+
+        // 原有配置中的用户名及密码迁移为管理员，默认密码 admin123 在启动时替换为随机密码
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"INSERT INTO "user" ("username", "password", "role") VALUES (
+                COALESCE((SELECT "value" FROM "config" WHERE "key" = 'username'), 'admin'),
+                COALESCE((SELECT "value" FROM "config" WHERE "key" = 'password'),
+                    '240be518fabd2724ddb6f04eeb1da5967448d7e831c08c8fa822809f74c720a9'),
+                0
+            )"#,
+        )
+        .await?;
+        db.execute_unprepared(r#"DELETE FROM "config" WHERE "key" IN ('username', 'password')"#)
+            .await?;
+        Ok(())
+    }
+}
+
+fn user() -> TableCreateStatement {
+    create_table("user")
+        .if_not_exists()
+        .col(id().primary_key())
+        .col(column("username").string().not_null().unique_key())
+        .col(column("password").string().not_null())
+        .col(column("role").integer().not_null())
+        .to_owned()
+}
+
+fn session() -> TableCreateStatement {
+    create_table("session")
+        .if_not_exists()
+        .col(column("token").string().not_null().primary_key())
+        .col(column("user_id").unsigned().not_null())
+        .col(column("expires_at").timestamp_with_time_zone().not_null())
+        .to_owned()
+}
+
+fn api_key() -> TableCreateStatement {
+    create_table("api_key")
+        .if_not_exists()
+        .col(id().primary_key())
+        .col(column("user_id").unsigned().not_null())
+        .col(column("name").string().not_null())
+        .col(column("key").string().not_null().unique_key())
+        .col(column("prefix").string().not_null())
+        .col(column("created_at").timestamp_with_time_zone().not_null())
+        .col(column("last_used_at").timestamp_with_time_zone().null())
+        .to_owned()
+}
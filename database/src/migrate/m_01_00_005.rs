@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+use super::*;
+
+#[derive(DeriveMigrationName)]
+pub(super) struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(user()).await?;
+
+        // 仍在使用默认密码 admin123 的用户需要在首次登录时修改密码，默认密码在启动时替换为随机密码
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"UPDATE "user" SET "must_change_password" = 1 WHERE "password" =
+                '240be518fabd2724ddb6f04eeb1da5967448d7e831c08c8fa822809f74c720a9'"#,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+fn user() -> TableAlterStatement {
+    alter_table("user")
+        .add_column(
+            column("must_change_password")
+                .boolean()
+                .not_null()
+                .default(false),
+        )
+        .to_owned()
+}
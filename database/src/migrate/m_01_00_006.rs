@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+use super::*;
+
+#[derive(DeriveMigrationName)]
+pub(super) struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(audit_log()).await?;
+        Ok(())
+    }
+}
+
+fn audit_log() -> TableCreateStatement {
+    create_table("audit_log")
+        .if_not_exists()
+        .col(id().primary_key())
+        .col(column("created_at").timestamp_with_time_zone().not_null())
+        .col(column("username").string().not_null())
+        .col(column("ip").string().not_null())
+        .col(column("action").string().not_null())
+        .col(column("target").string().not_null())
+        .col(column("success").boolean().not_null())
+        .to_owned()
+}
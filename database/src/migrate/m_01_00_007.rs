@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+use super::*;
+
+#[derive(DeriveMigrationName)]
+pub(super) struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(notification()).await?;
+        Ok(())
+    }
+}
+
+fn notification() -> TableCreateStatement {
+    create_table("notification")
+        .if_not_exists()
+        .col(id().primary_key())
+        .col(column("name").string().not_null())
+        .col(column("channel").integer().not_null())
+        .col(column("config").json().not_null())
+        .col(column("events").json().not_null())
+        .col(column("enable").boolean().not_null().default(true))
+        .to_owned()
+}
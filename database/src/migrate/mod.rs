@@ -1,12 +1,28 @@
 use sea_orm_migration::prelude::*;
 
 mod m_01_00_000;
+mod m_01_00_001;
+mod m_01_00_002;
+mod m_01_00_003;
+mod m_01_00_004;
+mod m_01_00_005;
+mod m_01_00_006;
+mod m_01_00_007;
 
 pub(crate) struct Migrator;
 
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m_01_00_000::Migration)]
+        vec![
+            Box::new(m_01_00_000::Migration),
+            Box::new(m_01_00_001::Migration),
+            Box::new(m_01_00_002::Migration),
+            Box::new(m_01_00_003::Migration),
+            Box::new(m_01_00_004::Migration),
+            Box::new(m_01_00_005::Migration),
+            Box::new(m_01_00_006::Migration),
+            Box::new(m_01_00_007::Migration),
+        ]
     }
 }
 
@@ -35,3 +51,10 @@ fn create_table(name: &'static str) -> TableCreateStatement {
     table.table(name);
     table
 }
+
+fn alter_table(name: &'static str) -> TableAlterStatement {
+    let name = IdenVal(name);
+    let mut table = Table::alter();
+    table.table(name);
+    table
+}
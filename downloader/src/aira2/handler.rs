@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use anyhow::{bail, Result};
 use serde::de::{DeserializeOwned, IgnoredAny};
 use serde::Serialize;
@@ -6,15 +8,24 @@ use serde_json::{json, Value};
 use encode::base64_encode;
 
 use super::receiver::{DownloadStatus, Response};
+use super::socket::Socket;
 use super::{AR, CLIENT};
 
+/// tellWaiting 及 tellStopped 每页数量
+const PAGE_SIZE: usize = 1000;
+
 fn list_fields() -> Value {
     json!([
         "gid",
         "infoHash",
         "totalLength",
         "completedLength",
+        "uploadLength",
+        "downloadSpeed",
+        "uploadSpeed",
+        "connections",
         "status",
+        "dir",
     ])
 }
 
@@ -24,7 +35,12 @@ fn detail_fields() -> Value {
         "infoHash",
         "totalLength",
         "completedLength",
+        "uploadLength",
+        "downloadSpeed",
+        "uploadSpeed",
+        "connections",
         "status",
+        "dir",
         "files",
     ])
 }
@@ -32,7 +48,7 @@ fn detail_fields() -> Value {
 #[derive(Debug, Serialize)]
 struct Request {
     jsonrpc: &'static str,
-    id: &'static str,
+    id: String,
     method: &'static str,
     params: Value,
 }
@@ -51,6 +67,64 @@ impl AR {
         .await
     }
 
+    pub(super) async fn add_uri(&self, uri: &str, dir: &str) -> Result<String> {
+        self.rpc("aria2.addUri", move |param| {
+            param.push([uri].as_slice().into());
+            param.push(json!({ "dir": dir }));
+        })
+        .await
+    }
+
+    pub(crate) async fn pause(&self, id: &str) -> Result<()> {
+        let _: IgnoredAny = self
+            .rpc("aria2.pause", |param| param.push(id.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub(super) async fn unpause(&self, id: &str) -> Result<()> {
+        let _: IgnoredAny = self
+            .rpc("aria2.unpause", |param| param.push(id.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub(super) async fn force_remove(&self, id: &str) -> Result<()> {
+        let _: IgnoredAny = self
+            .rpc("aria2.forceRemove", |param| param.push(id.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub(super) async fn remove_download_result(&self, id: &str) -> Result<()> {
+        let _: IgnoredAny = self
+            .rpc("aria2.removeDownloadResult", |param| param.push(id.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub(super) async fn change_position(&self, id: &str, how: &'static str) -> Result<()> {
+        let _: IgnoredAny = self
+            .rpc("aria2.changePosition", |param| {
+                param.push(id.into());
+                param.push(0.into());
+                param.push(how.into());
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// 修改活动中下载项的 select-file 等选项时，aria2 会自动重启该下载项
+    pub(super) async fn change_option(&self, id: &str, options: Value) -> Result<()> {
+        let _: IgnoredAny = self
+            .rpc("aria2.changeOption", |param| {
+                param.push(id.into());
+                param.push(options);
+            })
+            .await?;
+        Ok(())
+    }
+
     pub(super) async fn tell_status(&self, id: &str) -> Result<DownloadStatus> {
         self.rpc("aria2.tellStatus", |param| {
             param.push(id.into());
@@ -67,21 +141,31 @@ impl AR {
     }
 
     pub(super) async fn tell_waiting(&self) -> Result<Vec<DownloadStatus>> {
-        self.rpc("aria2.tellWaiting", |param| {
-            param.push(0.into());
-            param.push(1000.into());
-            param.push(list_fields());
-        })
-        .await
+        self.tell_paged("aria2.tellWaiting").await
     }
 
     pub(super) async fn tell_stopped(&self) -> Result<Vec<DownloadStatus>> {
-        self.rpc("aria2.tellStopped", |param| {
-            param.push(0.into());
-            param.push(1000.into());
-            param.push(list_fields());
-        })
-        .await
+        self.tell_paged("aria2.tellStopped").await
+    }
+
+    /// 分页获取全部下载项，避免下载项过多时被截断
+    async fn tell_paged(&self, method: &'static str) -> Result<Vec<DownloadStatus>> {
+        let mut list = Vec::new();
+        loop {
+            let offset = list.len();
+            let page: Vec<DownloadStatus> = self
+                .rpc(method, |param| {
+                    param.push(offset.into());
+                    param.push(PAGE_SIZE.into());
+                    param.push(list_fields());
+                })
+                .await?;
+            let count = page.len();
+            list.extend(page);
+            if count < PAGE_SIZE {
+                return Ok(list);
+            }
+        }
     }
 }
 
@@ -91,6 +175,18 @@ impl AR {
     }
 
     async fn rpc<F, T>(&self, method: &'static str, param_fn: F) -> Result<T>
+    where
+        F: FnOnce(&mut Vec<Value>),
+        T: DeserializeOwned,
+    {
+        let resp = self.try_rpc(method, param_fn).await;
+        if resp.is_err() {
+            crate::rpc_failed("aria2");
+        }
+        resp
+    }
+
+    async fn try_rpc<F, T>(&self, method: &'static str, param_fn: F) -> Result<T>
     where
         F: FnOnce(&mut Vec<Value>) -> (),
         T: DeserializeOwned,
@@ -101,17 +197,35 @@ impl AR {
         }
         param_fn(&mut param);
 
-        let req = CLIENT.post(&self.url).json(&Request {
+        // websocket 连接中需要使用 id 区分响应
+        static REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+        let id = REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let req = Request {
             jsonrpc: "2.0",
-            id: "mikanarr",
+            id: format!("mikanarr-{id}"),
             method,
             params: param.into(),
-        });
+        };
+
         // aira2 鉴权失败需要用户提供新的 secure
         // 不需要重复多次获取 session 等信息
-        let resp = req.send().await?;
+        let resp = match self.is_websocket() {
+            true => {
+                let body = serde_json::to_string(&req)?;
+                Socket.call(self.id, &self.url, req.id, body).await?
+            }
+            false => {
+                CLIENT
+                    .post(&self.url)
+                    .json(&req)
+                    .send()
+                    .await?
+                    .json()
+                    .await?
+            }
+        };
 
-        let resp: Response<T> = resp.json().await?;
+        let resp: Response<T> = serde_json::from_value(resp)?;
         match resp {
             Response::Ok { result } => Ok(result),
             Response::Error { error } => bail!("{}", error.message),
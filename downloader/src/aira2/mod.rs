@@ -1,26 +1,42 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use once_cell::sync::Lazy as LazyLock;
 use reqwest::Client;
+use serde_json::json;
+use tokio::sync::broadcast;
 
 use database::entity::Downloader;
 
-use crate::DownloadItem;
+use crate::{DownloadItem, DownloadSource, ItemPriority};
+
+use receiver::DownloadStatus;
+pub(crate) use socket::Socket;
 
 mod handler;
 mod receiver;
+mod socket;
 
 static CLIENT: LazyLock<Client> = LazyLock::new(Client::default);
 
 /// aira2 client
 /// [技术规范](https://aria2.github.io/manual/en/html/aria2c.html#methods)
+///
+/// 地址为 `ws://` 或 `wss://` 时使用 websocket 连接，下载状态变化时可以及时收到通知
+///
+/// aira2 没有分类或标签，所有下载项均保存在与分类同名的子目录下（包括路由规则指定的路径），
+/// 获取下载列表时仅保留保存路径最后一级目录为分类名的下载项
 pub(crate) struct AR {
-    _id: u32,
+    id: u32,
     url: String,
     secret: Option<String>,
     download_dir: String,
+    category: String,
 }
 
 impl AR {
+    pub(crate) fn id(&self) -> u32 {
+        self.id
+    }
+
     pub(crate) fn download_dir(&self) -> &str {
         self.download_dir.as_str()
     }
@@ -29,8 +45,25 @@ impl AR {
         self.get_version().await
     }
 
-    pub(crate) async fn download(&self, torrent: &[u8], dir: &str) -> Result<()> {
-        self.add_torrent(torrent, dir).await.map(|_| ())
+    fn is_websocket(&self) -> bool {
+        self.url.starts_with("ws://") || self.url.starts_with("wss://")
+    }
+
+    /// 订阅事件通知，仅 websocket 连接可用
+    pub(crate) async fn notifications(&self) -> Result<broadcast::Receiver<()>> {
+        if !self.is_websocket() {
+            bail!("aira2 event notification requires websocket rpc url");
+        }
+        Socket.subscribe(self.id, &self.url).await
+    }
+
+    pub(crate) async fn download(&self, source: DownloadSource<'_>, dir: &str) -> Result<()> {
+        let dir = &category_dir(dir, &self.category);
+        let gid = match source {
+            DownloadSource::Torrent(torrent) => self.add_torrent(torrent, dir).await,
+            DownloadSource::Magnet(uri) | DownloadSource::Url(uri) => self.add_uri(uri, dir).await,
+        };
+        gid.map(|_| ())
     }
 
     pub(crate) async fn download_list(&self) -> Result<Vec<DownloadItem>> {
@@ -41,24 +74,89 @@ impl AR {
         ];
         let list = vec.into_iter();
         let list = list.flatten();
+        let list = list.filter(|it| self.match_category(it));
         let list = list.map(|it| it.into());
         Ok(list.collect())
     }
 
+    /// 是否为当前分类的下载项，分类为空时匹配所有下载项
+    fn match_category(&self, status: &DownloadStatus) -> bool {
+        self.category.is_empty() || status.match_dir_name(&self.category)
+    }
+
     pub(crate) async fn download_files(&mut self, id: &str) -> Result<Vec<String>> {
         let status = self.tell_status(id).await?;
         let item = DownloadItem::from(status);
         Ok(item.relative_path)
     }
+
+    pub(crate) async fn resume(&self, id: &str) -> Result<()> {
+        self.unpause(id).await
+    }
+
+    pub(crate) async fn remove(&self, id: &str, delete_data: bool) -> Result<()> {
+        // aira2 rpc 不提供删除文件的方法
+        if delete_data {
+            bail!("aira2 unsupported remove downloaded data");
+        }
+        // 已停止的下载项仅需移除下载结果
+        match self.tell_status(id).await?.is_stopped() {
+            true => self.remove_download_result(id).await,
+            false => self.force_remove(id).await,
+        }
+    }
+
+    pub(crate) async fn set_priority(&self, id: &str, priority: ItemPriority) -> Result<()> {
+        match priority {
+            ItemPriority::High => self.change_position(id, "POS_SET").await,
+            ItemPriority::Low => self.change_position(id, "POS_END").await,
+        }
+    }
+
+    pub(crate) async fn set_files_wanted(&self, id: &str, wanted: &[usize]) -> Result<()> {
+        // aira2 select-file 下标从 1 开始
+        let select = wanted.iter().map(|it| (it + 1).to_string());
+        let select = select.collect::<Vec<_>>().join(",");
+        self.change_option(id, json!({ "select-file": select }))
+            .await
+    }
+
+    pub(crate) async fn set_seed_ratio_limit(&self, id: &str, ratio: Option<f64>) -> Result<()> {
+        // seed-ratio 为 0.0 时不限制分享率
+        let ratio = ratio.unwrap_or(0.0).to_string();
+        self.change_option(id, json!({ "seed-ratio": ratio })).await
+    }
+
+    pub(crate) async fn set_seed_time_limit(&self, id: &str, minutes: Option<u32>) -> Result<()> {
+        // seed-time 为 0 时会直接停止做种，aira2 无法取消已设置的做种时间
+        let Some(minutes) = minutes else {
+            bail!("aira2 unsupported unlimited seed time");
+        };
+        self.change_option(id, json!({ "seed-time": minutes.to_string() }))
+            .await
+    }
 }
 
 impl From<Downloader> for AR {
     fn from(value: Downloader) -> Self {
+        let download_dir = category_dir(&value.download_dir, &value.category);
         Self {
-            _id: value.id,
+            id: value.id,
             url: value.url,
             secret: value.password,
-            download_dir: value.download_dir,
+            download_dir,
+            category: value.category,
         }
     }
 }
+
+/// 分类不为空时，在下载路径下添加与分类同名的子目录，已位于此子目录时不重复添加
+fn category_dir(dir: &str, category: &str) -> String {
+    let base = dir.trim_end_matches(['/', '\\']);
+    let last = base.rsplit(['/', '\\']).next().unwrap_or(base);
+    match category {
+        "" => dir.to_owned(),
+        _ if last == category => base.to_owned(),
+        _ => format!("{base}/{category}"),
+    }
+}
@@ -1,4 +1,9 @@
-use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
 
 use crate::{DownloadItem, ItemStatus};
 
@@ -14,6 +19,17 @@ pub(super) enum Response<T> {
     Error { error: RespError },
 }
 
+/// aira2 rpc 返回的数字均为字符串形式
+fn number_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = Cow::<str>::deserialize(deserializer)?;
+    value.parse().map_err(D::Error::custom)
+}
+
 #[derive(Debug, Deserialize)]
 struct DownloadFileInfo {
     path: String,
@@ -22,17 +38,42 @@ struct DownloadFileInfo {
 #[derive(Debug, Deserialize)]
 pub(super) struct DownloadStatus {
     gid: String,
-    #[serde(rename = "infoHash")]
+    /// 非 BitTorrent 下载（如 http 下载 torrent 文件）无此字段
+    #[serde(rename = "infoHash", default)]
     info_hash: String,
-    #[serde(rename = "totalLength")]
+    #[serde(rename = "totalLength", deserialize_with = "number_str")]
     total: u64,
-    #[serde(rename = "completedLength")]
+    #[serde(rename = "completedLength", deserialize_with = "number_str")]
     completed: u64,
+    #[serde(rename = "uploadLength", deserialize_with = "number_str")]
+    uploaded: u64,
+    #[serde(rename = "downloadSpeed", deserialize_with = "number_str")]
+    download_speed: u64,
+    #[serde(rename = "uploadSpeed", deserialize_with = "number_str")]
+    upload_speed: u64,
+    #[serde(deserialize_with = "number_str")]
+    connections: u32,
     status: String,
     #[serde(default)]
+    dir: String,
+    #[serde(default)]
     files: Vec<DownloadFileInfo>,
 }
 
+impl DownloadStatus {
+    /// 下载项是否已停止（完成、错误或已移除）
+    pub(super) fn is_stopped(&self) -> bool {
+        matches!(self.status.as_str(), "complete" | "error" | "removed")
+    }
+
+    /// 下载路径的最后一级目录名是否为指定名称
+    pub(super) fn match_dir_name(&self, name: &str) -> bool {
+        let dir = self.dir.trim_end_matches(['/', '\\']);
+        let last = dir.rsplit(['/', '\\']).next().unwrap_or(dir);
+        last == name
+    }
+}
+
 impl From<DownloadStatus> for DownloadItem {
     fn from(value: DownloadStatus) -> Self {
         let status = match value.status.as_str() {
@@ -42,12 +83,45 @@ impl From<DownloadStatus> for DownloadItem {
             "complete" => ItemStatus::Complete,
             _ => ItemStatus::Error,
         };
-        let paths = value.files.into_iter().map(|it| it.path);
+        let progress = match value.total {
+            0 => 0.0,
+            total => value.completed as f64 / total as f64,
+        };
+        let ratio = match value.completed {
+            0 => 0.0,
+            completed => value.uploaded as f64 / completed as f64,
+        };
+        let eta = match value.download_speed {
+            0 => None,
+            speed => Some(value.total.saturating_sub(value.completed) / speed),
+        };
+        // aira2 返回的文件路径为完整路径，去除保存路径部分
+        let dir = value.dir;
+        let paths = value
+            .files
+            .into_iter()
+            .map(|it| match it.path.strip_prefix(&dir) {
+                Some(path) => path.trim_start_matches(['/', '\\']).to_owned(),
+                None => it.path,
+            });
         DownloadItem {
             id: value.gid,
             info_hash: value.info_hash,
             status,
             relative_path: paths.collect(),
+            save_path: dir,
+            local_path: String::default(),
+            size: value.total,
+            downloaded: value.completed,
+            progress,
+            download_speed: value.download_speed,
+            upload_speed: value.upload_speed,
+            ratio,
+            eta,
+            peers: value.connections,
+            // aira2 不记录添加及完成时间
+            added_at: None,
+            completed_at: None,
         }
     }
 }
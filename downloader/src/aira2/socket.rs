@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex as AsyncMutex};
+use tokio::time::{sleep, timeout};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 等待 rpc 响应的超时时间
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+/// 断线重连的最大间隔
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// 待发送的 rpc 请求
+struct Outgoing {
+    id: String,
+    body: String,
+    reply: oneshot::Sender<Value>,
+}
+
+/// websocket 连接信息
+struct SocketInfo {
+    url: String,
+    sender: mpsc::UnboundedSender<Outgoing>,
+    events: broadcast::Sender<()>,
+}
+
+/// aira2 websocket 连接，rpc 请求及事件通知共用同一个连接
+///
+/// 事件通知仅用于触发下载状态的重新获取，不区分通知类型
+///
+/// 每个下载器保持一个连接，连接断开后在下次请求时重新连接；
+/// 存在事件订阅者时会在后台自动重连，保证事件不中断
+pub(crate) struct Socket;
+
+impl Socket {
+    fn inner(&self) -> MutexGuard<'static, HashMap<u32, SocketInfo>> {
+        static SOCKET: OnceLock<Mutex<HashMap<u32, SocketInfo>>> = OnceLock::new();
+        SOCKET.get_or_init(Default::default).lock().unwrap()
+    }
+
+    /// 每个下载器的连接锁，保证同一下载器同时只建立一个连接
+    fn connect_lock(&self, downloader_id: u32) -> Arc<AsyncMutex<()>> {
+        static LOCK: OnceLock<Mutex<HashMap<u32, Arc<AsyncMutex<()>>>>> = OnceLock::new();
+        let mut lock = LOCK.get_or_init(Default::default).lock().unwrap();
+        lock.entry(downloader_id).or_default().clone()
+    }
+
+    /// 移除下载器的连接，下载器修改或删除后调用，连接处理任务随之结束
+    pub(crate) fn remove(&self, downloader_id: u32) {
+        self.inner().remove(&downloader_id);
+    }
+
+    /// 发送 rpc 请求，返回原始响应
+    pub(super) async fn call(
+        &self,
+        downloader_id: u32,
+        url: &str,
+        id: String,
+        body: String,
+    ) -> Result<Value> {
+        let sender = self.connect(downloader_id, url).await?;
+        let (reply, receiver) = oneshot::channel();
+        let outgoing = Outgoing { id, body, reply };
+        sender
+            .send(outgoing)
+            .ok()
+            .context("aira2 websocket closed")?;
+        let resp = timeout(RPC_TIMEOUT, receiver).await;
+        let resp = resp.context("aira2 websocket rpc timeout")?;
+        resp.context("aira2 websocket closed")
+    }
+
+    /// 订阅事件通知，aira2 下载状态变化时收到通知
+    pub(super) async fn subscribe(
+        &self,
+        downloader_id: u32,
+        url: &str,
+    ) -> Result<broadcast::Receiver<()>> {
+        self.connect(downloader_id, url).await?;
+        let inner = self.inner();
+        let info = inner
+            .get(&downloader_id)
+            .context("aira2 websocket closed")?;
+        Ok(info.events.subscribe())
+    }
+
+    /// 获取可用的连接，连接不存在、已断开或地址变化时重新连接
+    ///
+    /// 建立连接期间持有连接锁，避免并发请求各自连接并相互覆盖
+    async fn connect(
+        &self,
+        downloader_id: u32,
+        url: &str,
+    ) -> Result<mpsc::UnboundedSender<Outgoing>> {
+        let lock = self.connect_lock(downloader_id);
+        let _guard = lock.lock().await;
+        let events = match self.inner().get(&downloader_id) {
+            Some(info) if info.url == url && !info.sender.is_closed() => {
+                return Ok(info.sender.clone());
+            }
+            // 地址未变化时保留事件通道，已有的订阅者可以继续接收事件
+            Some(info) if info.url == url => info.events.clone(),
+            _ => broadcast::channel(64).0,
+        };
+
+        let (socket, _) = connect_async(url).await?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let url = url.to_owned();
+        tokio::spawn(run(
+            downloader_id,
+            url.clone(),
+            socket,
+            receiver,
+            events.clone(),
+        ));
+
+        let info = SocketInfo {
+            url,
+            sender: sender.clone(),
+            events,
+        };
+        self.inner().insert(downloader_id, info);
+        Ok(sender)
+    }
+}
+
+/// 连接处理任务，所有请求发送方关闭（连接被移除）后结束
+async fn run(
+    downloader_id: u32,
+    url: String,
+    mut socket: WebSocket,
+    mut receiver: mpsc::UnboundedReceiver<Outgoing>,
+    events: broadcast::Sender<()>,
+) {
+    let mut delay = Duration::from_secs(1);
+    loop {
+        if !serve(downloader_id, socket, &mut receiver, &events).await {
+            return;
+        }
+        // 没有事件订阅者时不需要保持连接，等待下次请求时重新连接
+        loop {
+            if events.receiver_count() == 0 || receiver.is_closed() {
+                return;
+            }
+            sleep(delay).await;
+            if receiver.is_closed() {
+                return;
+            }
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+            match connect_async(&url).await {
+                Ok((it, _)) => {
+                    socket = it;
+                    delay = Duration::from_secs(1);
+                    break;
+                }
+                Err(e) => log::warn!("aira2({downloader_id}) websocket reconnect failed: {e}"),
+            }
+        }
+    }
+}
+
+/// 处理单个连接的收发，连接断开时返回 true，请求发送方关闭时返回 false
+async fn serve(
+    downloader_id: u32,
+    socket: WebSocket,
+    receiver: &mut mpsc::UnboundedReceiver<Outgoing>,
+    events: &broadcast::Sender<()>,
+) -> bool {
+    let (mut write, mut read) = socket.split();
+    // 连接断开时丢弃未完成的请求，请求方会收到连接关闭错误
+    let mut pending = HashMap::new();
+    loop {
+        tokio::select! {
+            outgoing = receiver.recv() => {
+                let Some(outgoing) = outgoing else {
+                    return false;
+                };
+                if write.send(Message::Text(outgoing.body)).await.is_err() {
+                    return true;
+                }
+                pending.insert(outgoing.id, outgoing.reply);
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle(downloader_id, &text, &mut pending, events);
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    log::info!("aira2({downloader_id}) websocket disconnected");
+                    return true;
+                }
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+/// 处理 aira2 发送的消息，包含 rpc 响应及事件通知
+fn handle(
+    downloader_id: u32,
+    text: &str,
+    pending: &mut HashMap<String, oneshot::Sender<Value>>,
+    events: &broadcast::Sender<()>,
+) {
+    let value: Value = match serde_json::from_str(text) {
+        Ok(it) => it,
+        Err(e) => return log::warn!("aira2({downloader_id}) invalid websocket message: {e}"),
+    };
+
+    // 事件通知没有 id 字段，没有订阅者时发送失败，忽略即可
+    let Some(id) = value.get("id").and_then(Value::as_str) else {
+        let _ = events.send(());
+        return;
+    };
+
+    if let Some(reply) = pending.remove(id) {
+        let _ = reply.send(value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_remove_closes_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/jsonrpc", listener.local_addr().unwrap());
+        let (closed, on_closed) = oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = accept_async(stream).await.unwrap();
+            while let Some(Ok(_)) = socket.next().await {}
+            let _ = closed.send(());
+        });
+
+        // 存在事件订阅者时连接不会因为空闲而关闭
+        let _events = Socket.subscribe(u32::MAX, &url).await.unwrap();
+        Socket.remove(u32::MAX);
+        let closed = timeout(Duration::from_secs(5), on_closed).await;
+        assert!(closed.is_ok(), "websocket not closed after remove");
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use librqbit::{ManagedTorrent, Session, SessionOptions, SessionPersistenceConfig};
+use serde::{Deserialize, Serialize};
+use tokio::io::{copy, sink};
+use tokio::task::AbortHandle;
+use tokio::time::sleep;
+
+use database::entity::Config;
+
+/// 检查做种限制的间隔
+const SEED_LIMIT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 当前 unix 时间戳（秒）
+pub(super) fn now() -> i64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH);
+    now.map_or(0, |it| it.as_secs() as i64)
+}
+
+/// torrent 附加信息，librqbit 不记录这些信息
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(super) struct TorrentMeta {
+    /// 添加时的保存路径
+    pub(super) save_path: String,
+    /// 多文件 torrent 所在的子目录
+    pub(super) folder: Option<String>,
+    pub(super) added_at: i64,
+    pub(super) completed_at: Option<i64>,
+    /// 分享率限制，为空时不限制
+    pub(super) ratio_limit: Option<f64>,
+    /// 做种时间限制（分钟），为空时不限制
+    pub(super) seed_minutes: Option<u32>,
+    /// 达到做种限制后已暂停
+    pub(super) seeding_finished: bool,
+    /// 是否按顺序下载
+    #[serde(default)]
+    pub(super) sequential: bool,
+}
+
+/// 内置下载引擎，每个下载器一个 librqbit 会话
+pub(super) struct Engine {
+    pub(super) session: Arc<Session>,
+    /// 启动时使用的下载路径及监听端口，变化时重新启动
+    download_dir: String,
+    listen_ports: Range<u16>,
+    meta_path: PathBuf,
+    meta: Mutex<HashMap<String, TorrentMeta>>,
+    /// 顺序下载的读取任务
+    sequential: Mutex<HashMap<String, AbortHandle>>,
+}
+
+impl Engine {
+    /// 获取下载器的下载引擎，首次使用或下载路径、监听端口变化时启动
+    pub(super) async fn get(downloader_id: u32, download_dir: &str) -> Result<Arc<Self>> {
+        type Engines = tokio::sync::Mutex<HashMap<u32, Arc<Engine>>>;
+        static ENGINES: OnceLock<Engines> = OnceLock::new();
+
+        let listen_ports = Config.embedded_listen_ports().await;
+        let mut engines = ENGINES.get_or_init(Default::default).lock().await;
+        match engines.remove(&downloader_id) {
+            Some(engine)
+                if engine.download_dir == download_dir && engine.listen_ports == listen_ports =>
+            {
+                engines.insert(downloader_id, engine.clone());
+                return Ok(engine);
+            }
+            Some(engine) => {
+                log::info!("embedded downloader({downloader_id}) config changed, restarting");
+                engine.stop();
+            }
+            None => {}
+        }
+        let engine = Self::start(downloader_id, download_dir, listen_ports).await?;
+        let engine = Arc::new(engine);
+        engine.resume_sequential();
+        tokio::spawn(seed_limit(Arc::downgrade(&engine)));
+        engines.insert(downloader_id, engine.clone());
+        Ok(engine)
+    }
+
+    async fn start(
+        downloader_id: u32,
+        download_dir: &str,
+        listen_ports: Range<u16>,
+    ) -> Result<Self> {
+        let state_dir = database::app_data_dir().join("embedded");
+        let state_dir = state_dir.join(downloader_id.to_string());
+        std::fs::create_dir_all(&state_dir)?;
+        log::info!(
+            "embedded downloader({downloader_id}) data path: `{}`",
+            state_dir.display()
+        );
+
+        // 会话数据持久化，重启后恢复已添加的 torrent
+        let persistence = SessionPersistenceConfig::Json {
+            folder: Some(state_dir.join("session")),
+        };
+        let opts = SessionOptions {
+            fastresume: true,
+            persistence: Some(persistence),
+            listen_port_range: Some(listen_ports.clone()),
+            ..Default::default()
+        };
+        let session = Session::new_with_opts(download_dir.into(), opts).await?;
+
+        let meta_path = state_dir.join("torrents.json");
+        let meta = match std::fs::read(&meta_path) {
+            Ok(it) => serde_json::from_slice(&it)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => HashMap::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            session,
+            download_dir: download_dir.to_owned(),
+            listen_ports,
+            meta_path,
+            meta: Mutex::new(meta),
+            sequential: Mutex::default(),
+        })
+    }
+
+    /// 停止会话及顺序下载任务，不暂停 torrent，重新启动后保持原有状态
+    fn stop(&self) {
+        for (_, task) in self.sequential.lock().unwrap().drain() {
+            task.abort();
+        }
+        self.session.cancellation_token().cancel();
+    }
+
+    /// 开启或关闭 torrent 的顺序下载
+    pub(super) fn set_sequential(&self, torrent: &Arc<ManagedTorrent>, enable: bool) {
+        let hash = torrent.info_hash().as_string();
+        let mut tasks = self.sequential.lock().unwrap();
+        if let Some(task) = tasks.remove(&hash) {
+            task.abort();
+        }
+        if enable {
+            let task = tokio::spawn(read_in_order(torrent.clone()));
+            tasks.insert(hash, task.abort_handle());
+        }
+    }
+
+    /// 启动时恢复顺序下载任务
+    fn resume_sequential(&self) {
+        for torrent in self.torrents() {
+            if self.meta(&torrent.info_hash().as_string()).sequential {
+                self.set_sequential(&torrent, true);
+            }
+        }
+    }
+
+    /// 所有 torrent
+    pub(super) fn torrents(&self) -> Vec<Arc<ManagedTorrent>> {
+        let torrents = |it: &mut dyn Iterator<Item = (usize, &Arc<ManagedTorrent>)>| {
+            it.map(|(_, torrent)| torrent.clone()).collect()
+        };
+        self.session.with_torrents(torrents)
+    }
+
+    pub(super) fn meta(&self, hash: &str) -> TorrentMeta {
+        let meta = self.meta.lock().unwrap();
+        meta.get(hash).cloned().unwrap_or_default()
+    }
+
+    pub(super) fn update_meta(
+        &self,
+        hash: &str,
+        update: impl FnOnce(&mut TorrentMeta),
+    ) -> Result<()> {
+        let mut meta = self.meta.lock().unwrap();
+        update(meta.entry(hash.to_owned()).or_default());
+        self.save_meta(&meta)
+    }
+
+    pub(super) fn remove_meta(&self, hash: &str) -> Result<()> {
+        let mut meta = self.meta.lock().unwrap();
+        meta.remove(hash);
+        self.save_meta(&meta)
+    }
+
+    fn save_meta(&self, meta: &HashMap<String, TorrentMeta>) -> Result<()> {
+        std::fs::write(&self.meta_path, serde_json::to_vec(meta)?)?;
+        Ok(())
+    }
+
+    /// 记录完成时间，达到做种限制的 torrent 暂停做种
+    async fn check_seed_limit(&self) -> Result<()> {
+        let now = now();
+        for torrent in self.torrents() {
+            let stats = torrent.stats();
+            if !stats.finished {
+                continue;
+            }
+            let hash = torrent.info_hash().as_string();
+            let meta = self.meta(&hash);
+            let Some(completed_at) = meta.completed_at else {
+                self.update_meta(&hash, |it| it.completed_at = Some(now))?;
+                continue;
+            };
+            if meta.seeding_finished || torrent.is_paused() {
+                continue;
+            }
+
+            let ratio = match stats.total_bytes {
+                0 => 0.0,
+                total => stats.uploaded_bytes as f64 / total as f64,
+            };
+            let ratio_reached = meta.ratio_limit.is_some_and(|it| ratio >= it);
+            let seed_seconds = now - completed_at;
+            let time_reached = meta
+                .seed_minutes
+                .is_some_and(|it| seed_seconds >= i64::from(it) * 60);
+            if ratio_reached || time_reached {
+                self.session.pause(&torrent).await?;
+                self.update_meta(&hash, |it| it.seeding_finished = true)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 定时检查做种限制，引擎释放后结束
+async fn seed_limit(engine: Weak<Engine>) {
+    loop {
+        sleep(SEED_LIMIT_INTERVAL).await;
+        let Some(engine) = engine.upgrade() else {
+            return;
+        };
+        if let Err(e) = engine.check_seed_limit().await {
+            log::warn!("embedded downloader seed limit check failed: {e}");
+        }
+    }
+}
+
+/// 按文件顺序读取 torrent 内容，librqbit 会优先下载读取位置之后的分片，以此实现顺序下载
+///
+/// 文件顺序与 librqbit 默认的下载顺序一致（按文件名排序），已完成或未选择的文件跳过
+async fn read_in_order(torrent: Arc<ManagedTorrent>) {
+    let hash = torrent.info_hash().as_string();
+    if let Err(e) = torrent.wait_until_initialized().await {
+        return log::warn!("embedded torrent({hash}) sequential download failed: {e}");
+    }
+    let files = torrent.with_metadata(|it| {
+        let files = it.file_infos.iter().enumerate();
+        let mut files = files
+            .map(|(id, file)| (id, file.relative_filename.clone(), file.len))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.1.cmp(&b.1));
+        files
+    });
+    let Ok(files) = files else {
+        return;
+    };
+    for (id, _, len) in files {
+        let selected = torrent.only_files().map_or(true, |it| it.contains(&id));
+        let progress = torrent.stats().file_progress.get(id).copied();
+        if !selected || progress.is_some_and(|it| it >= len) {
+            continue;
+        }
+        let result = async {
+            let mut stream = torrent.clone().stream(id)?;
+            copy(&mut stream, &mut sink()).await?;
+            anyhow::Ok(())
+        };
+        if let Err(e) = result.await {
+            return log::warn!("embedded torrent({hash}) sequential download failed: {e}");
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use librqbit::api::TorrentIdOrHash;
+use librqbit::{
+    AddTorrent, AddTorrentOptions, AddTorrentResponse, ListOnlyResponse, ManagedTorrent,
+    TorrentStatsState,
+};
+
+use database::entity::{Config, Downloader};
+
+use crate::{DownloadItem, DownloadSource, ItemStatus};
+
+use engine::{now, Engine, TorrentMeta};
+
+mod engine;
+
+/// 内置下载器，基于 [librqbit](https://github.com/ikatson/rqbit)
+///
+/// 不需要单独部署下载器，下载器 url 等连接信息不使用，会话数据保存在应用数据目录下。
+/// 下载器仅包含 mikanarr 添加的 torrent，不区分分类
+///
+/// 默认使用 librqbit 的分片选择（按文件名顺序，每个文件优先下载首尾分片），
+/// 开启顺序下载时严格按文件内容顺序下载，便于边下边播
+pub(crate) struct EM {
+    id: u32,
+    download_dir: String,
+}
+
+impl EM {
+    pub(crate) fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub(crate) fn download_dir(&self) -> &str {
+        self.download_dir.as_str()
+    }
+
+    async fn engine(&self) -> Result<Arc<Engine>> {
+        Engine::get(self.id, &self.download_dir).await
+    }
+
+    async fn torrent(&self, id: &str) -> Result<(Arc<Engine>, Arc<ManagedTorrent>)> {
+        let engine = self.engine().await?;
+        let torrent = engine.session.get(TorrentIdOrHash::parse(id)?);
+        let torrent = torrent.with_context(|| format!("Can't find torrent `{id}`"))?;
+        Ok((engine, torrent))
+    }
+
+    pub(crate) async fn connect_test(&self) -> Result<()> {
+        self.engine().await.map(|_| ())
+    }
+
+    pub(crate) async fn download(&self, source: DownloadSource<'_>, dir: &str) -> Result<()> {
+        let engine = self.engine().await?;
+        let add = match source {
+            DownloadSource::Torrent(torrent) => AddTorrent::from_bytes(torrent.to_vec()),
+            DownloadSource::Magnet(url) | DownloadSource::Url(url) => {
+                AddTorrent::from_url(url.to_owned())
+            }
+        };
+
+        // 先解析 torrent 信息，多文件 torrent 与其他下载器一致保存在以名称命名的子目录中
+        let opts = AddTorrentOptions {
+            list_only: true,
+            ..Default::default()
+        };
+        let resp = engine.session.add_torrent(add, Some(opts)).await?;
+        let AddTorrentResponse::ListOnly(info) = resp else {
+            bail!("embedded downloader can't resolve torrent");
+        };
+        let folder = torrent_folder(&info)?;
+        let output_folder = match &folder {
+            Some(folder) => Path::new(dir).join(folder),
+            None => PathBuf::from(dir),
+        };
+
+        let opts = AddTorrentOptions {
+            output_folder: Some(output_folder.to_string_lossy().into_owned()),
+            overwrite: true,
+            initial_peers: Some(info.seen_peers),
+            ..Default::default()
+        };
+        let add = AddTorrent::from_bytes(info.torrent_bytes);
+        let resp = engine.session.add_torrent(add, Some(opts)).await?;
+        // 已存在时保留原有信息
+        if let AddTorrentResponse::Added(_, torrent) = resp {
+            let hash = torrent.info_hash().as_string();
+            let sequential = Config.embedded_sequential().await;
+            engine.update_meta(&hash, |it| {
+                *it = TorrentMeta {
+                    save_path: dir.to_owned(),
+                    folder,
+                    added_at: now(),
+                    sequential,
+                    ..Default::default()
+                };
+            })?;
+            engine.set_sequential(&torrent, sequential);
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn download_list(&self) -> Result<Vec<DownloadItem>> {
+        let engine = self.engine().await?;
+        let list = engine.torrents().into_iter();
+        let list = list.map(|it| download_item(&it, engine.meta(&it.info_hash().as_string())));
+        Ok(list.collect())
+    }
+
+    pub(crate) async fn download_files(&mut self, id: &str) -> Result<Vec<String>> {
+        let (engine, torrent) = self.torrent(id).await?;
+        let meta = engine.meta(&torrent.info_hash().as_string());
+        Ok(relative_path(&torrent, &meta))
+    }
+
+    pub(crate) async fn pause(&self, id: &str) -> Result<()> {
+        let (engine, torrent) = self.torrent(id).await?;
+        engine.session.pause(&torrent).await
+    }
+
+    pub(crate) async fn resume(&self, id: &str) -> Result<()> {
+        let (engine, torrent) = self.torrent(id).await?;
+        // 手动恢复时重新开始做种，仍然超过做种限制时会再次暂停
+        let hash = torrent.info_hash().as_string();
+        engine.update_meta(&hash, |it| it.seeding_finished = false)?;
+        engine.session.unpause(&torrent).await
+    }
+
+    pub(crate) async fn remove(&self, id: &str, delete_data: bool) -> Result<()> {
+        let (engine, torrent) = self.torrent(id).await?;
+        let hash = torrent.info_hash().as_string();
+        engine.set_sequential(&torrent, false);
+        engine
+            .session
+            .delete(TorrentIdOrHash::parse(id)?, delete_data)
+            .await?;
+        engine.remove_meta(&hash)
+    }
+
+    pub(crate) async fn set_files_wanted(&self, id: &str, wanted: &[usize]) -> Result<()> {
+        let (engine, torrent) = self.torrent(id).await?;
+        let wanted = wanted.iter().copied().collect::<HashSet<_>>();
+        engine.session.update_only_files(&torrent, &wanted).await
+    }
+
+    /// 开启时严格按文件内容顺序下载，关闭时使用 librqbit 默认的分片选择
+    pub(crate) async fn set_sequential(&self, id: &str, enable: bool) -> Result<()> {
+        let (engine, torrent) = self.torrent(id).await?;
+        let hash = torrent.info_hash().as_string();
+        engine.update_meta(&hash, |it| it.sequential = enable)?;
+        engine.set_sequential(&torrent, enable);
+        Ok(())
+    }
+
+    pub(crate) async fn set_seed_ratio_limit(&self, id: &str, ratio: Option<f64>) -> Result<()> {
+        let (engine, torrent) = self.torrent(id).await?;
+        let hash = torrent.info_hash().as_string();
+        engine.update_meta(&hash, |it| it.ratio_limit = ratio)
+    }
+
+    pub(crate) async fn set_seed_time_limit(&self, id: &str, minutes: Option<u32>) -> Result<()> {
+        let (engine, torrent) = self.torrent(id).await?;
+        let hash = torrent.info_hash().as_string();
+        engine.update_meta(&hash, |it| it.seed_minutes = minutes)
+    }
+}
+
+/// 多文件 torrent 使用的子目录，单文件 torrent 直接保存在下载路径中
+fn torrent_folder(info: &ListOnlyResponse) -> Result<Option<String>> {
+    if info.info.iter_file_details()?.count() < 2 {
+        return Ok(None);
+    }
+    let name = info
+        .info
+        .name
+        .as_ref()
+        .map(|it| String::from_utf8_lossy(it.as_ref()));
+    let name = name.filter(|it| !it.is_empty() && !it.contains(['/', '\\']) && it != "..");
+    let name = name.map_or_else(|| info.info_hash.as_string(), |it| it.into_owned());
+    Ok(Some(name))
+}
+
+/// 文件相对路径（相对于保存路径）
+fn relative_path(torrent: &ManagedTorrent, meta: &TorrentMeta) -> Vec<String> {
+    let paths = torrent.with_metadata(|it| {
+        let paths = it.file_infos.iter().map(|file| match &meta.folder {
+            Some(folder) => Path::new(folder).join(&file.relative_filename),
+            None => file.relative_filename.clone(),
+        });
+        paths.map(|it| it.to_string_lossy().into_owned()).collect()
+    });
+    // magnet 元数据未解析完成时没有文件信息
+    paths.unwrap_or_default()
+}
+
+fn download_item(torrent: &ManagedTorrent, meta: TorrentMeta) -> DownloadItem {
+    let stats = torrent.stats();
+    let status = match stats.state {
+        TorrentStatsState::Error => ItemStatus::Error,
+        _ if stats.error.is_some() => ItemStatus::Error,
+        _ if stats.finished && meta.seeding_finished => ItemStatus::Complete,
+        _ if stats.finished => ItemStatus::Downloaded,
+        _ => ItemStatus::Downloading,
+    };
+    let progress = match stats.total_bytes {
+        0 => 0.0,
+        total => stats.progress_bytes as f64 / total as f64,
+    };
+    let ratio = match stats.total_bytes {
+        0 => 0.0,
+        total => stats.uploaded_bytes as f64 / total as f64,
+    };
+    // librqbit 速度单位为 MiB/s
+    let speed = |mbps: f64| (mbps * 1024.0 * 1024.0) as u64;
+    let live = stats.live.as_ref();
+    let download_speed = live.map_or(0, |it| speed(it.download_speed.mbps));
+    let upload_speed = live.map_or(0, |it| speed(it.upload_speed.mbps));
+    let eta = match download_speed {
+        0 => None,
+        speed => Some(stats.total_bytes.saturating_sub(stats.progress_bytes) / speed),
+    };
+    let peers = live.map_or(0, |it| it.snapshot.peer_stats.live as u32);
+    let hash = torrent.info_hash().as_string();
+    DownloadItem {
+        id: hash.clone(),
+        info_hash: hash,
+        status,
+        relative_path: relative_path(torrent, &meta),
+        save_path: meta.save_path,
+        local_path: String::default(),
+        size: stats.total_bytes,
+        downloaded: stats.progress_bytes,
+        progress,
+        download_speed,
+        upload_speed,
+        ratio,
+        eta,
+        peers,
+        added_at: Some(meta.added_at).filter(|it| *it > 0),
+        completed_at: meta.completed_at,
+    }
+}
+
+impl From<Downloader> for EM {
+    fn from(value: Downloader) -> Self {
+        Self {
+            id: value.id,
+            download_dir: value.download_dir,
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+use tokio::task::JoinSet;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+use database::entity::Downloader;
+use eventbus::{DownloadEvent, Event, EventBus};
+
+use crate::{DownloadClient, DownloadItem, ItemStatus};
+
+/// 轮询下载状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 下载事件流，下载器被删除时结束
+pub struct EventStream(Receiver<DownloadEvent>);
+
+impl EventStream {
+    /// 等待下一个事件，事件流结束时返回 `None`
+    pub async fn next(&mut self) -> Option<DownloadEvent> {
+        loop {
+            match self.0.recv().await {
+                Ok(event) => return Some(event),
+                // 处理过慢时会丢弃旧的事件，继续接收新的事件
+                Err(RecvError::Lagged(count)) => log::warn!("download event lagged {count}"),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// 下载项状态，用于对比两次轮询之间的变化
+pub(crate) struct ItemState {
+    pub(crate) info_hash: String,
+    pub(crate) status: ItemStatus,
+    pub(crate) progress: f64,
+    pub(crate) download_speed: u64,
+}
+
+impl From<&DownloadItem> for ItemState {
+    fn from(value: &DownloadItem) -> Self {
+        Self {
+            info_hash: value.info_hash.clone(),
+            status: value.status,
+            progress: value.progress,
+            download_speed: value.download_speed,
+        }
+    }
+}
+
+/// 下载状态监视，每个下载器共用一个轮询任务，没有订阅者时结束
+///
+/// 支持推送的下载器（aira2 websocket）收到通知时立即轮询
+pub(crate) struct Watcher;
+
+impl Watcher {
+    fn inner(&self) -> MutexGuard<'static, HashMap<u32, Sender<DownloadEvent>>> {
+        static WATCHER: OnceLock<Mutex<HashMap<u32, Sender<DownloadEvent>>>> = OnceLock::new();
+        WATCHER.get_or_init(Default::default).lock().unwrap()
+    }
+
+    pub(crate) fn subscribe(&self, downloader_id: u32) -> EventStream {
+        let mut inner = self.inner();
+        if let Some(sender) = inner.get(&downloader_id) {
+            return EventStream(sender.subscribe());
+        }
+        let (sender, receiver) = broadcast::channel(256);
+        inner.insert(downloader_id, sender.clone());
+        tokio::spawn(watch(downloader_id, sender));
+        EventStream(receiver)
+    }
+
+    /// 没有订阅者时移除，返回是否已移除
+    fn remove_unused(&self, downloader_id: u32, sender: &Sender<DownloadEvent>) -> bool {
+        let mut inner = self.inner();
+        if sender.receiver_count() > 0 {
+            return false;
+        }
+        inner.remove(&downloader_id);
+        true
+    }
+
+    fn remove(&self, downloader_id: u32) {
+        self.inner().remove(&downloader_id);
+    }
+}
+
+/// 检查新增下载器的间隔
+const WATCH_ALL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 持续监视所有下载器，下载事件发布到事件总线，token 取消后结束
+pub async fn watch_all(token: CancellationToken) {
+    let mut watching = HashSet::new();
+    let mut streams = JoinSet::new();
+    while !token.is_cancelled() {
+        match Downloader::find_all().await {
+            Ok(list) => {
+                for downloader_id in list.into_iter().map(|it| it.id) {
+                    if !watching.insert(downloader_id) {
+                        continue;
+                    }
+                    let mut stream = Watcher.subscribe(downloader_id);
+                    streams.spawn(async move {
+                        while stream.next().await.is_some() {}
+                        downloader_id
+                    });
+                }
+            }
+            Err(e) => log::warn!("load downloaders failed: {e}"),
+        }
+        tokio::select! {
+            _ = sleep(WATCH_ALL_INTERVAL) => {}
+            // 下载器被删除时事件流结束，之后重新添加时可以再次监视
+            Some(Ok(downloader_id)) = streams.join_next() => {
+                watching.remove(&downloader_id);
+            }
+            _ = token.cancelled() => {}
+        }
+    }
+}
+
+async fn watch(downloader_id: u32, sender: Sender<DownloadEvent>) {
+    let mut previous: Option<HashMap<String, ItemState>> = None;
+    let mut notifications = None;
+    loop {
+        if Watcher.remove_unused(downloader_id, &sender) {
+            return;
+        }
+        // 每次轮询重新读取下载器配置，下载器被删除时结束
+        let downloader = match Downloader::find_by_id(downloader_id).await {
+            Ok(Some(it)) => it,
+            Ok(None) => return Watcher.remove(downloader_id),
+            Err(e) => {
+                log::warn!("downloader({downloader_id}) load failed: {e}");
+                sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+        let mut client = DownloadClient::from(downloader);
+        if notifications.is_none() {
+            notifications = client.notifications().await;
+        }
+
+        match client.item_states().await {
+            Ok(current) => {
+                if let Some(previous) = &previous {
+                    for event in diff(downloader_id, previous, &current) {
+                        EventBus.publish(Event::Download(event.clone()));
+                        // 没有订阅者时发送失败，下次轮询时结束
+                        let _ = sender.send(event);
+                    }
+                }
+                previous = Some(current);
+            }
+            Err(e) => log::warn!("downloader({downloader_id}) poll failed: {e}"),
+        }
+
+        let Some(receiver) = &mut notifications else {
+            sleep(POLL_INTERVAL).await;
+            continue;
+        };
+        tokio::select! {
+            _ = sleep(POLL_INTERVAL) => {}
+            result = receiver.recv() => {
+                // 连接关闭时丢弃，下次轮询时重新订阅
+                if let Err(RecvError::Closed) = result {
+                    notifications = None;
+                }
+            }
+        }
+    }
+}
+
+/// 对比两次轮询的下载状态，生成下载事件
+fn diff(
+    downloader_id: u32,
+    previous: &HashMap<String, ItemState>,
+    current: &HashMap<String, ItemState>,
+) -> Vec<DownloadEvent> {
+    let mut events = Vec::new();
+    for (id, state) in current {
+        let id = id.clone();
+        let info_hash = state.info_hash.clone();
+        let Some(old) = previous.get(&id) else {
+            events.push(DownloadEvent::Added {
+                downloader_id,
+                id,
+                info_hash,
+            });
+            continue;
+        };
+        match (old.status, state.status) {
+            (ItemStatus::Downloading, ItemStatus::Downloading)
+                if old.progress != state.progress =>
+            {
+                events.push(DownloadEvent::Progress {
+                    downloader_id,
+                    id,
+                    info_hash,
+                    progress: state.progress,
+                    download_speed: state.download_speed,
+                });
+            }
+            (ItemStatus::Downloading, ItemStatus::Downloaded) => {
+                events.push(DownloadEvent::Completed {
+                    downloader_id,
+                    id,
+                    info_hash,
+                });
+            }
+            (ItemStatus::Downloading, ItemStatus::Complete) => {
+                events.push(DownloadEvent::Completed {
+                    downloader_id,
+                    id: id.clone(),
+                    info_hash: info_hash.clone(),
+                });
+                events.push(DownloadEvent::SeedingFinished {
+                    downloader_id,
+                    id,
+                    info_hash,
+                });
+            }
+            (ItemStatus::Downloaded, ItemStatus::Complete) => {
+                events.push(DownloadEvent::SeedingFinished {
+                    downloader_id,
+                    id,
+                    info_hash,
+                });
+            }
+            (old, ItemStatus::Error) if old != ItemStatus::Error => {
+                events.push(DownloadEvent::Errored {
+                    downloader_id,
+                    id,
+                    info_hash,
+                });
+            }
+            _ => {}
+        }
+    }
+    let removed = previous.iter().filter(|(id, _)| !current.contains_key(*id));
+    let removed = removed.map(|(id, state)| DownloadEvent::Removed {
+        downloader_id,
+        id: id.clone(),
+        info_hash: state.info_hash.clone(),
+    });
+    events.extend(removed);
+    events
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn state(status: ItemStatus, progress: f64) -> ItemState {
+        ItemState {
+            info_hash: String::new(),
+            status,
+            progress,
+            download_speed: 0,
+        }
+    }
+
+    /// 下载项 id 为 gid 等与 hash 不同的值
+    fn states(list: &[(&str, ItemStatus, f64)]) -> HashMap<String, ItemState> {
+        let list = list.iter().map(|(id, s, p)| {
+            let state = ItemState {
+                info_hash: format!("hash_{id}"),
+                ..state(*s, *p)
+            };
+            (id.to_string(), state)
+        });
+        list.collect()
+    }
+
+    fn diff_one(old: ItemState, new: ItemState) -> Vec<DownloadEvent> {
+        let previous = HashMap::from([("a".to_string(), old)]);
+        let current = HashMap::from([("a".to_string(), new)]);
+        diff(1, &previous, &current)
+    }
+
+    #[test]
+    fn test_diff_added_removed() {
+        let previous = states(&[("a", ItemStatus::Downloading, 0.5)]);
+        let current = states(&[("b", ItemStatus::Downloading, 0.0)]);
+        let events = diff(1, &previous, &current);
+        assert!(matches!(
+            &events[..],
+            [DownloadEvent::Added { downloader_id: 1, id: added, info_hash: added_hash },
+             DownloadEvent::Removed { downloader_id: 1, id: removed, info_hash: removed_hash }]
+            if added == "b" && added_hash == "hash_b" && removed == "a" && removed_hash == "hash_a"
+        ));
+        assert!(diff(1, &current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_diff_status() {
+        use ItemStatus::*;
+
+        let events = diff_one(state(Downloading, 0.1), state(Downloading, 0.2));
+        assert!(
+            matches!(events[..], [DownloadEvent::Progress { progress, .. }] if progress == 0.2)
+        );
+        assert!(diff_one(state(Downloading, 0.2), state(Downloading, 0.2)).is_empty());
+
+        let events = diff_one(state(Downloading, 0.9), state(Downloaded, 1.0));
+        assert!(matches!(events[..], [DownloadEvent::Completed { .. }]));
+        let events = diff_one(state(Downloading, 0.9), state(Complete, 1.0));
+        assert!(matches!(
+            events[..],
+            [
+                DownloadEvent::Completed { .. },
+                DownloadEvent::SeedingFinished { .. }
+            ]
+        ));
+        let events = diff_one(state(Downloaded, 1.0), state(Complete, 1.0));
+        assert!(matches!(
+            events[..],
+            [DownloadEvent::SeedingFinished { .. }]
+        ));
+
+        let events = diff_one(state(Downloaded, 1.0), state(Error, 1.0));
+        assert!(matches!(events[..], [DownloadEvent::Errored { .. }]));
+        assert!(diff_one(state(Error, 1.0), state(Error, 1.0)).is_empty());
+        // 手动暂停或恢复做种不产生事件
+        assert!(diff_one(state(Complete, 1.0), state(Downloaded, 1.0)).is_empty());
+    }
+}
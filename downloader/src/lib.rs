@@ -1,15 +1,30 @@
+use std::collections::HashMap;
+
 use anyhow::{bail, Result};
+use serde::Serialize;
+use tokio::sync::broadcast;
 
 use database::entity::{Downloader, DownloaderType};
+use eventbus::{Event, EventBus};
+
+pub use event::{watch_all, EventStream};
+pub use eventbus::DownloadEvent;
+
+use event::{ItemState, Watcher};
+pub use path::PathMapper;
+pub use route::RoutedClient;
 
 mod aira2;
+mod embedded;
+mod event;
+mod path;
 mod qbittorrent;
+mod route;
 mod transmission;
 
-/// 下载类别，用于区分获取下载种子列表
-static DEFAULT_CATEGORY: &str = "mikanarr";
-
 /// 下载状态
+#[derive(Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ItemStatus {
     /// 下载中
     Downloading,
@@ -21,7 +36,36 @@ pub enum ItemStatus {
     Error,
 }
 
+/// 下载来源
+pub enum DownloadSource<'a> {
+    /// torrent 文件内容
+    Torrent(&'a [u8]),
+    /// magnet 链接
+    Magnet(&'a str),
+    /// torrent 文件的 http(s) 下载地址
+    Url(&'a str),
+}
+
+impl<'a> DownloadSource<'a> {
+    /// 根据下载地址类型生成下载来源，magnet 链接以外的均视为 http 地址
+    pub fn from_url(url: &'a str) -> Self {
+        match url.starts_with("magnet:") {
+            true => Self::Magnet(url),
+            false => Self::Url(url),
+        }
+    }
+}
+
+/// 下载项队列优先级
+pub enum ItemPriority {
+    /// 移至队列首位
+    High,
+    /// 移至队列末位
+    Low,
+}
+
 /// 下载项信息
+#[derive(Serialize)]
 pub struct DownloadItem {
     /// 下载项 id
     pub id: String,
@@ -29,8 +73,32 @@ pub struct DownloadItem {
     pub info_hash: String,
     /// 下载状态
     pub status: ItemStatus,
-    /// 文件相对路径
+    /// 文件相对路径（相对于保存路径）
     pub relative_path: Vec<String>,
+    /// 保存路径（下载器中的路径）
+    pub save_path: String,
+    /// 保存路径（经过路径映射后的本地路径）
+    pub local_path: String,
+    /// 需要下载的总大小（字节）
+    pub size: u64,
+    /// 已下载大小（字节）
+    pub downloaded: u64,
+    /// 下载进度，范围 0.0 ~ 1.0
+    pub progress: f64,
+    /// 下载速度（字节/秒）
+    pub download_speed: u64,
+    /// 上传速度（字节/秒）
+    pub upload_speed: u64,
+    /// 分享率
+    pub ratio: f64,
+    /// 预计剩余下载时间（秒），未知时为空
+    pub eta: Option<u64>,
+    /// 已连接的 peer 数量
+    pub peers: u32,
+    /// 添加时间（unix 时间戳，秒），下载器不支持时为空
+    pub added_at: Option<i64>,
+    /// 完成时间（unix 时间戳，秒），未完成或下载器不支持时为空
+    pub completed_at: Option<i64>,
 }
 
 /// 下载客户端
@@ -40,15 +108,39 @@ enum DownloaderInner {
     Aira2(aira2::AR),
     Qbittorrent(qbittorrent::QB),
     Transmission(transmission::TR),
+    Embedded(embedded::EM),
 }
 
 impl DownloadClient {
-    /// 下载文件夹基础路径
+    /// 下载器 id
+    pub fn id(&self) -> u32 {
+        match &self.0 {
+            DownloaderInner::Aira2(it) => it.id(),
+            DownloaderInner::Qbittorrent(it) => it.id(),
+            DownloaderInner::Transmission(it) => it.id(),
+            DownloaderInner::Embedded(it) => it.id(),
+        }
+    }
+
+    /// 清除下载器缓存的会话，下载器修改或删除后调用，保证使用新的地址及登录信息
+    pub fn invalidate(id: u32) {
+        aira2::Socket.remove(id);
+        qbittorrent::Session.remove(id);
+        transmission::Session.remove(id);
+    }
+
+    /// 获取此下载器的路径映射，用于将下载器中的路径转换为本地路径
+    pub async fn path_mapper(&self) -> Result<PathMapper> {
+        PathMapper::load(self.id()).await
+    }
+
+    /// 下载文件夹基础路径（下载器中的路径）
     pub fn download_dir(&self) -> &str {
         match &self.0 {
             DownloaderInner::Aira2(it) => it.download_dir(),
             DownloaderInner::Qbittorrent(it) => it.download_dir(),
             DownloaderInner::Transmission(it) => it.download_dir(),
+            DownloaderInner::Embedded(it) => it.download_dir(),
         }
     }
 
@@ -63,25 +155,58 @@ impl DownloadClient {
             DownloaderInner::Aira2(it) => it.connect_test().await,
             DownloaderInner::Qbittorrent(it) => it.connect_test().await,
             DownloaderInner::Transmission(it) => it.connect_test().await,
+            DownloaderInner::Embedded(it) => it.connect_test().await,
         }
     }
 
+    /// 订阅下载事件，同一下载器的订阅者共用一个轮询任务
+    pub fn subscribe(&self) -> EventStream {
+        Watcher.subscribe(self.id())
+    }
+
+    /// 下载器推送的事件通知，不支持推送时为空
+    async fn notifications(&self) -> Option<broadcast::Receiver<()>> {
+        match &self.0 {
+            DownloaderInner::Aira2(it) => it.notifications().await.ok(),
+            _ => None,
+        }
+    }
+
+    /// 获取所有下载项的状态，用于生成下载事件
+    async fn item_states(&mut self) -> Result<HashMap<String, ItemState>> {
+        let list = match &mut self.0 {
+            DownloaderInner::Aira2(it) => it.download_list().await?,
+            DownloaderInner::Qbittorrent(it) => return it.item_states().await,
+            DownloaderInner::Transmission(it) => it.download_list().await?,
+            DownloaderInner::Embedded(it) => it.download_list().await?,
+        };
+        let states = list.iter().map(|it| (it.id.clone(), ItemState::from(it)));
+        Ok(states.collect())
+    }
+
     /// 下载器添加 torrent
-    pub async fn download(&mut self, torrent: &[u8], dir: &str) -> Result<()> {
+    pub async fn download(&mut self, source: DownloadSource<'_>, dir: &str) -> Result<()> {
         match &mut self.0 {
-            DownloaderInner::Aira2(it) => it.download(torrent, dir).await,
-            DownloaderInner::Qbittorrent(it) => it.add_torrent(torrent, dir).await,
-            DownloaderInner::Transmission(it) => it.download(torrent, dir).await,
+            DownloaderInner::Aira2(it) => it.download(source, dir).await,
+            DownloaderInner::Qbittorrent(it) => it.add_torrent(source, dir).await,
+            DownloaderInner::Transmission(it) => it.download(source, dir).await,
+            DownloaderInner::Embedded(it) => it.download(source, dir).await,
         }
     }
 
     /// 获取下载列表
     pub async fn download_list(&mut self) -> Result<Vec<DownloadItem>> {
-        match &mut self.0 {
+        let mut list = match &mut self.0 {
             DownloaderInner::Aira2(it) => it.download_list().await,
             DownloaderInner::Qbittorrent(it) => it.download_list().await,
             DownloaderInner::Transmission(it) => it.download_list().await,
+            DownloaderInner::Embedded(it) => it.download_list().await,
+        }?;
+        let mapper = self.path_mapper().await?;
+        for item in list.iter_mut() {
+            item.local_path = mapper.to_local(&item.save_path);
         }
+        Ok(list)
     }
 
     /// 获取单个下载项的文件路径（相对路径）
@@ -90,6 +215,7 @@ impl DownloadClient {
             DownloaderInner::Aira2(it) => it.download_files(id).await,
             DownloaderInner::Qbittorrent(it) => it.download_files(id).await,
             DownloaderInner::Transmission(it) => it.download_files(id).await,
+            DownloaderInner::Embedded(it) => it.download_files(id).await,
         }
     }
 
@@ -97,8 +223,128 @@ impl DownloadClient {
     pub async fn rename_file(&mut self, id: &str, old_path: &str, new_path: &str) -> Result<()> {
         match &mut self.0 {
             DownloaderInner::Aira2(_) => bail!("aira2 unsupported rename file"),
-            DownloaderInner::Qbittorrent(it) => it.rename_file(id, old_path, new_path).await,
+            DownloaderInner::Qbittorrent(it) => it.rename_file(id, old_path, new_path).await?,
             DownloaderInner::Transmission(_) => bail!("transmission unsupported rename file"),
+            DownloaderInner::Embedded(_) => bail!("embedded unsupported rename file"),
+        }
+        self.renamed(id, old_path, new_path);
+        Ok(())
+    }
+
+    /// 重命名下载文件夹
+    pub async fn rename_folder(&mut self, id: &str, old_path: &str, new_path: &str) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(_) => bail!("aira2 unsupported rename folder"),
+            DownloaderInner::Qbittorrent(it) => it.rename_folder(id, old_path, new_path).await?,
+            DownloaderInner::Transmission(_) => bail!("transmission unsupported rename folder"),
+            DownloaderInner::Embedded(_) => bail!("embedded unsupported rename folder"),
+        }
+        self.renamed(id, old_path, new_path);
+        Ok(())
+    }
+
+    /// 仅 qbittorrent 支持重命名，下载项 id 即为 hash
+    fn renamed(&self, id: &str, old_path: &str, new_path: &str) {
+        EventBus.publish(Event::Download(DownloadEvent::Renamed {
+            downloader_id: self.id(),
+            id: id.to_string(),
+            info_hash: id.to_string(),
+            old_path: old_path.to_string(),
+            new_path: new_path.to_string(),
+        }));
+    }
+
+    /// 暂停下载项
+    pub async fn pause(&mut self, id: &str) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(it) => it.pause(id).await,
+            DownloaderInner::Qbittorrent(it) => it.pause(id).await,
+            DownloaderInner::Transmission(it) => it.pause(id).await,
+            DownloaderInner::Embedded(it) => it.pause(id).await,
+        }
+    }
+
+    /// 恢复下载项
+    pub async fn resume(&mut self, id: &str) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(it) => it.resume(id).await,
+            DownloaderInner::Qbittorrent(it) => it.resume(id).await,
+            DownloaderInner::Transmission(it) => it.resume(id).await,
+            DownloaderInner::Embedded(it) => it.resume(id).await,
+        }
+    }
+
+    /// 移除下载项，`delete_data` 为 true 时同时删除已下载的文件
+    pub async fn remove(&mut self, id: &str, delete_data: bool) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(it) => it.remove(id, delete_data).await,
+            DownloaderInner::Qbittorrent(it) => it.remove(id, delete_data).await,
+            DownloaderInner::Transmission(it) => it.remove(id, delete_data).await,
+            DownloaderInner::Embedded(it) => it.remove(id, delete_data).await,
+        }
+    }
+
+    /// 重新校验下载项
+    pub async fn recheck(&mut self, id: &str) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(_) => bail!("aira2 unsupported recheck"),
+            DownloaderInner::Qbittorrent(it) => it.recheck(id).await,
+            DownloaderInner::Transmission(it) => it.recheck(id).await,
+            DownloaderInner::Embedded(_) => bail!("embedded unsupported recheck"),
+        }
+    }
+
+    /// 设置下载项队列优先级
+    pub async fn set_priority(&mut self, id: &str, priority: ItemPriority) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(it) => it.set_priority(id, priority).await,
+            DownloaderInner::Qbittorrent(it) => it.set_priority(id, priority).await,
+            DownloaderInner::Transmission(it) => it.set_priority(id, priority).await,
+            DownloaderInner::Embedded(_) => bail!("embedded unsupported queue priority"),
+        }
+    }
+
+    /// 设置需要下载的文件，`wanted` 为 [`Self::download_files`] 中的文件下标，其余文件跳过
+    pub async fn set_files_wanted(&mut self, id: &str, wanted: &[usize]) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(it) => it.set_files_wanted(id, wanted).await,
+            DownloaderInner::Qbittorrent(it) => it.set_files_wanted(id, wanted).await,
+            DownloaderInner::Transmission(it) => it.set_files_wanted(id, wanted).await,
+            DownloaderInner::Embedded(it) => it.set_files_wanted(id, wanted).await,
+        }
+    }
+
+    /// 设置是否按顺序下载，仅内置下载器支持
+    pub async fn set_sequential(&mut self, id: &str, enable: bool) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(_) => bail!("aira2 unsupported sequential download"),
+            DownloaderInner::Qbittorrent(_) => bail!("qbittorrent unsupported sequential download"),
+            DownloaderInner::Transmission(_) => {
+                bail!("transmission unsupported sequential download")
+            }
+            DownloaderInner::Embedded(it) => it.set_sequential(id, enable).await,
+        }
+    }
+
+    /// 设置做种分享率限制，`None` 为不限制
+    pub async fn set_seed_ratio_limit(&mut self, id: &str, ratio: Option<f64>) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(it) => it.set_seed_ratio_limit(id, ratio).await,
+            DownloaderInner::Qbittorrent(it) => it.set_seed_ratio_limit(id, ratio).await,
+            DownloaderInner::Transmission(it) => it.set_seed_ratio_limit(id, ratio).await,
+            DownloaderInner::Embedded(it) => it.set_seed_ratio_limit(id, ratio).await,
+        }
+    }
+
+    /// 设置做种时间限制（分钟），`None` 为不限制
+    ///
+    /// transmission 仅支持空闲做种时间限制，此处设置的是空闲时间
+    pub async fn set_seed_time_limit(&mut self, id: &str, minutes: Option<u32>) -> Result<()> {
+        match &mut self.0 {
+            DownloaderInner::Aira2(it) => it.set_seed_time_limit(id, minutes).await,
+            DownloaderInner::Qbittorrent(it) => it.set_seed_time_limit(id, minutes).await,
+            DownloaderInner::Transmission(it) => it.set_seed_time_limit(id, minutes).await,
+            DownloaderInner::Embedded(it) => it.set_seed_time_limit(id, minutes).await,
         }
     }
 }
@@ -109,6 +355,12 @@ impl From<Downloader> for DownloadClient {
             DownloaderType::Aira2 => Self(DownloaderInner::Aira2(value.into())),
             DownloaderType::Qbittorrent => Self(DownloaderInner::Qbittorrent(value.into())),
             DownloaderType::Transmission => Self(DownloaderInner::Transmission(value.into())),
+            DownloaderType::Embedded => Self(DownloaderInner::Embedded(value.into())),
         }
     }
 }
+
+/// 记录下载器 rpc 请求失败次数
+fn rpc_failed(backend: &'static str) {
+    metrics::counter!("mikanarr_downloader_rpc_errors_total", "backend" => backend).increment(1);
+}
@@ -0,0 +1,88 @@
+use anyhow::Result;
+
+use database::entity::PathMapping;
+
+/// 下载器远程路径到本地路径的映射
+///
+/// 与 sonarr 的 remote path mapping 相同，使用最长匹配的远程路径前缀进行替换，
+/// 替换后的路径分隔符与本地路径前缀保持一致
+pub struct PathMapper(Vec<PathMapping>);
+
+impl PathMapper {
+    pub(crate) async fn load(downloader_id: u32) -> Result<Self> {
+        Ok(Self(PathMapping::find_by_downloader(downloader_id).await?))
+    }
+
+    /// 将下载器中的路径转换为本地路径，没有匹配的映射时原样返回
+    pub fn to_local(&self, remote: &str) -> String {
+        let matched = self.0.iter().filter_map(|it| {
+            let rest = strip_path_prefix(remote, &it.remote_path)?;
+            Some((it.remote_path.len(), it.local_path.as_str(), rest))
+        });
+        match matched.max_by_key(|(len, _, _)| *len) {
+            None => remote.to_owned(),
+            Some((_, local, rest)) => join_path(local, rest),
+        }
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+/// 去除路径前缀，前缀需要匹配完整的路径段
+fn strip_path_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let prefix = prefix.trim_end_matches(is_separator);
+    let rest = path.strip_prefix(prefix)?;
+    match rest.is_empty() || rest.starts_with(is_separator) {
+        true => Some(rest.trim_start_matches(is_separator)),
+        false => None,
+    }
+}
+
+/// 拼接路径，分隔符使用基础路径中的分隔符
+fn join_path(base: &str, rest: &str) -> String {
+    let separator = match base.contains('\\') && !base.contains('/') {
+        true => "\\",
+        false => "/",
+    };
+    let base = base.trim_end_matches(is_separator);
+    let rest = rest.split(is_separator).filter(|it| !it.is_empty());
+    let mut path = base.to_owned();
+    for segment in rest {
+        path.push_str(separator);
+        path.push_str(segment);
+    }
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mapper(mappings: &[(&str, &str)]) -> PathMapper {
+        let mappings = mappings.iter().map(|(remote, local)| PathMapping {
+            id: 0,
+            downloader_id: 0,
+            remote_path: remote.to_string(),
+            local_path: local.to_string(),
+        });
+        PathMapper(mappings.collect())
+    }
+
+    #[test]
+    fn test_to_local() {
+        let mapper = mapper(&[("/downloads", "/data"), ("/downloads/tv/", "/media/tv")]);
+        assert_eq!(mapper.to_local("/downloads/a/b.mkv"), "/data/a/b.mkv");
+        assert_eq!(mapper.to_local("/downloads/tv/b.mkv"), "/media/tv/b.mkv");
+        assert_eq!(mapper.to_local("/downloads"), "/data");
+        assert_eq!(mapper.to_local("/downloads2/a"), "/downloads2/a");
+    }
+
+    #[test]
+    fn test_to_local_separator() {
+        let mapper = mapper(&[("D:\\Downloads", "/mnt/d"), ("/seed", "\\\\nas\\seed")]);
+        assert_eq!(mapper.to_local("D:\\Downloads\\a\\b.mkv"), "/mnt/d/a/b.mkv");
+        assert_eq!(mapper.to_local("/seed/a/b.mkv"), "\\\\nas\\seed\\a\\b.mkv");
+    }
+}
@@ -1,19 +1,15 @@
-use anyhow::Result;
+use std::collections::HashMap;
+
+use anyhow::{ensure, Context, Result};
+use reqwest::multipart::{Form, Part};
 use reqwest::{RequestBuilder, Response, StatusCode};
 use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
-
-use crate::{DownloadItem, ItemStatus, DEFAULT_CATEGORY};
+use serde::Deserialize;
 
-use super::{CLIENT, QB};
+use crate::event::ItemState;
+use crate::{DownloadItem, DownloadSource, ItemPriority, ItemStatus};
 
-/// 下载 torrent 参数
-#[derive(Serialize)]
-struct TorrentAddArgs<'a> {
-    torrents: &'a [u8],
-    savepath: &'a str,
-    category: &'a str,
-}
+use super::{ApiVersion, QB};
 
 /// torrent 文件信息
 #[derive(Deserialize)]
@@ -22,28 +18,183 @@ pub(super) struct FileInfo {
     pub(super) name: String,
 }
 
+/// torrent 做种限制，-2 为使用全局设置，-1 为不限制
+#[derive(Deserialize)]
+pub(super) struct ShareLimits {
+    /// 分享率限制
+    pub(super) ratio_limit: f64,
+    /// 做种时间限制（分钟）
+    pub(super) seeding_time_limit: i64,
+    /// 空闲做种时间限制（分钟），WebAPI v2.9 之前不存在此字段
+    #[serde(default = "ShareLimits::global")]
+    pub(super) inactive_seeding_time_limit: i64,
+}
+
+impl ShareLimits {
+    pub(super) const GLOBAL: i64 = -2;
+    pub(super) const UNLIMITED: i64 = -1;
+
+    fn global() -> i64 {
+        Self::GLOBAL
+    }
+}
+
 /// torrent 信息
 #[derive(Deserialize)]
 pub(super) struct TorrentInfo {
     hash: String,
     state: String,
+    save_path: String,
+    size: u64,
+    downloaded: u64,
+    progress: f64,
+    dlspeed: u64,
+    upspeed: u64,
+    ratio: f64,
+    /// 实际生效的分享率限制，-1 为不限制
+    #[serde(default = "SeedLimit::unlimited_ratio")]
+    max_ratio: f64,
+    /// 做种时间（秒）
+    #[serde(default)]
+    seeding_time: i64,
+    /// 实际生效的做种时间限制（分钟），-1 为不限制
+    #[serde(default = "SeedLimit::unlimited_time")]
+    max_seeding_time: i64,
+    eta: u64,
+    num_seeds: u32,
+    num_leechs: u32,
+    added_on: i64,
+    completion_on: i64,
+}
+
+/// sync/maindata 中的 torrent 信息，增量更新时仅包含变化的字段
+#[derive(Clone, Default, Deserialize)]
+pub(super) struct SyncTorrent {
+    state: Option<String>,
+    progress: Option<f64>,
+    dlspeed: Option<u64>,
+    ratio: Option<f64>,
+    max_ratio: Option<f64>,
+    seeding_time: Option<i64>,
+    max_seeding_time: Option<i64>,
+    pub(super) category: Option<String>,
+}
+
+impl SyncTorrent {
+    pub(super) fn merge(&mut self, other: Self) {
+        self.state = other.state.or(self.state.take());
+        self.progress = other.progress.or(self.progress);
+        self.dlspeed = other.dlspeed.or(self.dlspeed);
+        self.ratio = other.ratio.or(self.ratio);
+        self.max_ratio = other.max_ratio.or(self.max_ratio);
+        self.seeding_time = other.seeding_time.or(self.seeding_time);
+        self.max_seeding_time = other.max_seeding_time.or(self.max_seeding_time);
+        self.category = other.category.or(self.category.take());
+    }
+}
+
+/// sync/maindata 中以 hash 为 key
+impl From<(String, SyncTorrent)> for ItemState {
+    fn from((hash, value): (String, SyncTorrent)) -> Self {
+        let limit = SeedLimit {
+            ratio: value.ratio.unwrap_or_default(),
+            max_ratio: value.max_ratio.unwrap_or(ShareLimits::UNLIMITED as f64),
+            seeding_time: value.seeding_time.unwrap_or_default(),
+            max_seeding_time: value.max_seeding_time.unwrap_or(ShareLimits::UNLIMITED),
+        };
+        let progress = value.progress.unwrap_or_default();
+        let state = value.state.as_deref().unwrap_or_default();
+        Self {
+            info_hash: hash,
+            status: item_status(state, progress, &limit),
+            progress,
+            download_speed: value.dlspeed.unwrap_or_default(),
+        }
+    }
+}
+
+/// sync/maindata 响应，rid 为 0 或数据过旧时为全量更新
+#[derive(Deserialize)]
+pub(super) struct MainData {
+    pub(super) rid: u64,
+    #[serde(default)]
+    pub(super) full_update: bool,
+    #[serde(default)]
+    pub(super) torrents: HashMap<String, SyncTorrent>,
+    #[serde(default)]
+    pub(super) torrents_removed: Vec<String>,
+}
+
+/// 做种限制及当前做种情况，用于判断 torrent 是否因达到限制而停止做种
+struct SeedLimit {
+    ratio: f64,
+    max_ratio: f64,
+    /// 做种时间（秒）
+    seeding_time: i64,
+    /// 做种时间限制（分钟）
+    max_seeding_time: i64,
+}
+
+impl SeedLimit {
+    fn unlimited_ratio() -> f64 {
+        ShareLimits::UNLIMITED as f64
+    }
+
+    fn unlimited_time() -> i64 {
+        ShareLimits::UNLIMITED
+    }
+
+    fn is_reached(&self) -> bool {
+        (self.max_ratio >= 0.0 && self.ratio >= self.max_ratio)
+            || (self.max_seeding_time >= 0 && self.seeding_time >= self.max_seeding_time * 60)
+    }
+}
+
+/// WebAPI v2.11 之后暂停状态更名为 stoppedDL 及 stoppedUP
+///
+/// 达到做种限制后暂停的视为做种完成，手动暂停的仍为下载完成
+fn item_status(state: &str, progress: f64, limit: &SeedLimit) -> ItemStatus {
+    match state {
+        "allocating" | "downloading" | "metaDL" | "pausedDL" | "stoppedDL" | "queuedDL"
+        | "stalledDL" | "checkingDL" | "forcedDL" | "checkingResumeData" => ItemStatus::Downloading,
+        "pausedUP" | "stoppedUP" if limit.is_reached() => ItemStatus::Complete,
+        "uploading" | "pausedUP" | "stoppedUP" | "queuedUP" | "stalledUP" | "checkingUP"
+        | "forcedUP" => ItemStatus::Downloaded,
+        // 移动保存路径中，按进度区分
+        "moving" if progress < 1.0 => ItemStatus::Downloading,
+        "moving" => ItemStatus::Downloaded,
+        _ => ItemStatus::Error,
+    }
 }
 
 impl From<TorrentInfo> for DownloadItem {
     fn from(value: TorrentInfo) -> Self {
-        let status = match value.state.as_str() {
-            "allocating" | "downloading" | "metaDL" | "pausedDL" | "queuedDL" | "stalledDL"
-            | "checkingDL" | "forcedDL" | "checkingResumeData" => ItemStatus::Downloading,
-            "uploading" | "pausedUP" | "queuedUP" | "stalledUP" | "checkingUP" | "forcedUP" => {
-                ItemStatus::Downloaded
-            }
-            _ => ItemStatus::Error,
+        let limit = SeedLimit {
+            ratio: value.ratio,
+            max_ratio: value.max_ratio,
+            seeding_time: value.seeding_time,
+            max_seeding_time: value.max_seeding_time,
         };
+        let status = item_status(&value.state, value.progress, &limit);
+        // eta 为 8640000 时表示无限
+        let eta = Some(value.eta).filter(|it| *it < 8640000);
         DownloadItem {
             id: value.hash.clone(),
             info_hash: value.hash,
             status,
             relative_path: Vec::default(),
+            save_path: value.save_path,
+            local_path: String::default(),
+            size: value.size,
+            downloaded: value.downloaded,
+            progress: value.progress,
+            download_speed: value.dlspeed,
+            upload_speed: value.upspeed,
+            ratio: value.ratio,
+            eta,
+            peers: value.num_seeds + value.num_leechs,
+            added_at: Some(value.added_on).filter(|it| *it > 0),
+            completed_at: Some(value.completion_on).filter(|it| *it > 0),
         }
     }
 }
@@ -51,42 +202,149 @@ impl From<TorrentInfo> for DownloadItem {
 impl QB {
     pub(super) async fn app_version(&self) -> Result<()> {
         let url = format!("{}/api/v2/app/version", self.url);
-        let req = CLIENT.get(url);
+        let req = self.client.get(url);
         self.api_without_resp(req).await
     }
 
-    pub(crate) async fn add_torrent(&self, torrent: &[u8], dir: &str) -> Result<()> {
+    pub(super) async fn webapi_version(&self) -> Result<ApiVersion> {
+        let url = format!("{}/api/v2/app/webapiVersion", self.url);
+        let req = self.client.get(url);
+        let resp = self.send(req).await?.error_for_status()?;
+        resp.text().await?.parse()
+    }
+
+    pub(crate) async fn add_torrent(&self, source: DownloadSource<'_>, dir: &str) -> Result<()> {
         let url = format!("{}/api/v2/torrents/add", self.url);
-        let req = CLIENT.post(url).form(&TorrentAddArgs {
-            torrents: torrent,
-            savepath: dir,
-            category: DEFAULT_CATEGORY,
-        });
+        let form = match source {
+            DownloadSource::Torrent(torrent) => {
+                let file = Part::bytes(torrent.to_vec()).file_name("mikanarr.torrent");
+                Form::new().part("torrents", file)
+            }
+            DownloadSource::Magnet(url) | DownloadSource::Url(url) => {
+                Form::new().text("urls", url.to_owned())
+            }
+        };
+        let mut form = form.text("savepath", dir.to_owned());
+        if !self.category.is_empty() {
+            form = form.text("category", self.category.clone());
+        }
+        // multipart 请求无法在鉴权失败后重试，先通过其他请求确认登录状态
+        self.app_version().await?;
+        let req = self.client.post(url).multipart(form);
         self.api_without_resp(req).await
     }
 
     pub(super) async fn torrent_info(&self) -> Result<Vec<TorrentInfo>> {
         let url = format!("{}/api/v2/torrents/info", self.url);
-        static FIXED_PARAM: [(&str, &str); 1] = [("category", DEFAULT_CATEGORY)];
-        let req = CLIENT.get(url).query(&FIXED_PARAM);
+        let mut req = self.client.get(url);
+        // 分类参数为空字符串时仅返回未分类的 torrent，此时不传递参数以获取所有 torrent
+        if !self.category.is_empty() {
+            req = req.query(&[("category", &self.category)]);
+        }
+        self.api(req).await
+    }
+
+    pub(super) async fn sync_maindata(&self, rid: u64) -> Result<MainData> {
+        let url = format!("{}/api/v2/sync/maindata", self.url);
+        let req = self.client.get(url).query(&[("rid", rid)]);
         Ok(self.api(req).await?)
     }
 
     pub(super) async fn torrent_files(&self, id: &str) -> Result<Vec<FileInfo>> {
         let url = format!("{}/api/v2/torrents/files", self.url);
-        let req = CLIENT.get(url).query(&[("hash", id)]);
+        let req = self.client.get(url).query(&[("hash", id)]);
         Ok(self.api(req).await?)
     }
 
-    pub(crate) async fn rename_file(&self, id: &str, old_path: &str, new_path: &str) -> Result<()> {
+    pub(super) async fn share_limits(&self, id: &str) -> Result<ShareLimits> {
+        let url = format!("{}/api/v2/torrents/info", self.url);
+        let req = self.client.get(url).query(&[("hashes", id)]);
+        let list: Vec<ShareLimits> = self.api(req).await?;
+        list.into_iter().next().context("Can't find torrent info")
+    }
+
+    pub(super) async fn set_share_limits(&self, id: &str, limits: ShareLimits) -> Result<()> {
+        let url = format!("{}/api/v2/torrents/setShareLimits", self.url);
+        let param = [
+            ("hashes", id.to_owned()),
+            ("ratioLimit", limits.ratio_limit.to_string()),
+            ("seedingTimeLimit", limits.seeding_time_limit.to_string()),
+            (
+                "inactiveSeedingTimeLimit",
+                limits.inactive_seeding_time_limit.to_string(),
+            ),
+        ];
+        let req = self.client.post(url).form(&param);
+        self.api_without_resp(req).await
+    }
+
+    pub(super) async fn file_prio(&self, id: &str, indexes: &[usize], priority: u8) -> Result<()> {
+        let url = format!("{}/api/v2/torrents/filePrio", self.url);
+        let indexes = indexes.iter().map(usize::to_string);
+        let indexes = indexes.collect::<Vec<_>>().join("|");
+        let priority = priority.to_string();
+        let param = [("hash", id), ("id", &indexes), ("priority", &priority)];
+        let req = self.client.post(url).form(&param);
+        self.api_without_resp(req).await
+    }
+
+    pub(crate) async fn recheck(&self, id: &str) -> Result<()> {
+        self.torrents_action("recheck", id).await
+    }
+
+    pub(crate) async fn remove(&self, id: &str, delete_data: bool) -> Result<()> {
+        let url = format!("{}/api/v2/torrents/delete", self.url);
+        let delete_files = if delete_data { "true" } else { "false" };
+        let param = [("hashes", id), ("deleteFiles", delete_files)];
+        let req = self.client.post(url).form(&param);
+        self.api_without_resp(req).await
+    }
+
+    /// 队列优先级调整需要在 qbittorrent 中开启 torrent 排队
+    pub(crate) async fn set_priority(&self, id: &str, priority: ItemPriority) -> Result<()> {
+        match priority {
+            ItemPriority::High => self.torrents_action("topPrio", id).await,
+            ItemPriority::Low => self.torrents_action("bottomPrio", id).await,
+        }
+    }
+
+    /// WebAPI v2.7 及之后版本可用
+    pub(super) async fn rename_file_by_path(&self, id: &str, old: &str, new: &str) -> Result<()> {
         let url = format!("{}/api/v2/torrents/renameFile", self.url);
-        let param = [("hash", id), ("oldPath", old_path), ("newPath", new_path)];
-        let req = CLIENT.post(url).form(&param);
+        let param = [("hash", id), ("oldPath", old), ("newPath", new)];
+        let req = self.client.post(url).form(&param);
+        self.api_without_resp(req).await
+    }
+
+    /// WebAPI v2.7 之前版本可用
+    pub(super) async fn rename_file_by_index(
+        &self,
+        id: &str,
+        index: usize,
+        new: &str,
+    ) -> Result<()> {
+        let url = format!("{}/api/v2/torrents/renameFile", self.url);
+        let index = index.to_string();
+        let param = [("hash", id), ("id", &index), ("name", new)];
+        let req = self.client.post(url).form(&param);
+        self.api_without_resp(req).await
+    }
+
+    /// WebAPI v2.7 及之后版本可用
+    pub(super) async fn rename_folder_by_path(&self, id: &str, old: &str, new: &str) -> Result<()> {
+        let url = format!("{}/api/v2/torrents/renameFolder", self.url);
+        let param = [("hash", id), ("oldPath", old), ("newPath", new)];
+        let req = self.client.post(url).form(&param);
+        self.api_without_resp(req).await
+    }
+
+    /// 仅需 hashes 参数的 torrent 操作
+    pub(super) async fn torrents_action(&self, action: &str, id: &str) -> Result<()> {
+        let url = format!("{}/api/v2/torrents/{action}", self.url);
+        let req = self.client.post(url).form(&[("hashes", id)]);
         self.api_without_resp(req).await
     }
-}
 
-impl QB {
     /// 尝试登录
     async fn login(&self) -> Result<()> {
         let username = self.username.as_ref().map(String::as_str);
@@ -97,16 +355,29 @@ impl QB {
             ("password", password.unwrap_or_default()),
         ];
 
-        let req = CLIENT.post(url).form(&param);
-        req.send().await?.error_for_status()?;
+        let req = self.client.post(url).form(&param);
+        let resp = req.send().await?.error_for_status()?;
+        // 登录失败时状态码仍为 200，需要通过响应内容判断
+        let text = resp.text().await?;
+        ensure!(text.trim() == "Ok.", "qbittorrent login failed: {text}");
         Ok(())
     }
 }
 
 impl QB {
     async fn send(&self, req: RequestBuilder) -> Result<Response> {
-        // 未使用 stream body，clone 不会失败
-        let first_req = req.try_clone().unwrap();
+        let resp = self.try_send(req).await;
+        if resp.is_err() {
+            crate::rpc_failed("qbittorrent");
+        }
+        resp
+    }
+
+    async fn try_send(&self, req: RequestBuilder) -> Result<Response> {
+        // multipart body 无法 clone，此时不做重试
+        let Some(first_req) = req.try_clone() else {
+            return Ok(req.send().await?);
+        };
         let resp = first_req.send().await?;
 
         // 如果鉴权失败，那么先尝试进行登录，之后再次进行请求
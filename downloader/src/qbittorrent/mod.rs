@@ -1,33 +1,162 @@
-use anyhow::Result;
-use once_cell::sync::Lazy as LazyLock;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use anyhow::{Context, Error, Result};
 use reqwest::Client;
 
 use database::entity::Downloader;
 
+use crate::event::ItemState;
 use crate::DownloadItem;
 
+use handler::{MainData, ShareLimits, SyncTorrent};
+
 mod handler;
 
-static CLIENT: LazyLock<Client> =
-    LazyLock::new(|| Client::builder().cookie_store(true).build().unwrap());
+/// WebAPI 版本
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct ApiVersion(u32, u32, u32);
+
+impl ApiVersion {
+    /// 新增 torrents/renameFolder，torrents/renameFile 参数改为 oldPath 及 newPath
+    const RENAME_PATH: Self = Self(2, 7, 0);
+    /// torrents/pause 及 torrents/resume 更名为 torrents/stop 及 torrents/start
+    const STOP_START: Self = Self(2, 11, 0);
+}
+
+impl FromStr for ApiVersion {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.trim().splitn(3, '.').map(str::parse::<u32>);
+        let major = parts.next().context("empty webapi version")??;
+        let minor = parts.next().transpose()?.unwrap_or_default();
+        let patch = parts.next().transpose()?.unwrap_or_default();
+        Ok(Self(major, minor, patch))
+    }
+}
+
+/// 下载器会话信息
+struct SessionInfo {
+    url: String,
+    client: Client,
+    version: Option<ApiVersion>,
+    /// sync/maindata 的响应 id 及合并后的 torrent 信息
+    sync_rid: u64,
+    sync_torrents: HashMap<String, SyncTorrent>,
+}
+
+impl SessionInfo {
+    fn new(url: &str) -> Self {
+        let client = Client::builder().cookie_store(true).build().unwrap();
+        Self {
+            url: url.to_owned(),
+            client,
+            version: None,
+            sync_rid: 0,
+            sync_torrents: HashMap::default(),
+        }
+    }
+}
+
+/// 每个下载器使用独立的 client，保证登录 cookie（SID）及 WebAPI 版本互不影响
+pub(crate) struct Session;
+
+impl Session {
+    fn inner(&self) -> MutexGuard<'static, HashMap<u32, SessionInfo>> {
+        static SESSION: OnceLock<Mutex<HashMap<u32, SessionInfo>>> = OnceLock::new();
+        SESSION.get_or_init(Default::default).lock().unwrap()
+    }
+
+    /// 获取下载器的 client，下载器 url 变化时重新创建会话
+    fn client(&self, id: u32, url: &str) -> Client {
+        let mut inner = self.inner();
+        let session = inner.entry(id).or_insert_with(|| SessionInfo::new(url));
+        if session.url != url {
+            *session = SessionInfo::new(url);
+        }
+        session.client.clone()
+    }
+
+    /// 清除下载器的会话，下次请求时重新登录
+    pub(crate) fn remove(&self, id: u32) {
+        self.inner().remove(&id);
+    }
+
+    fn version(&self, id: u32) -> Option<ApiVersion> {
+        self.inner().get(&id).and_then(|it| it.version)
+    }
+
+    fn set_version(&self, id: u32, version: ApiVersion) {
+        if let Some(session) = self.inner().get_mut(&id) {
+            session.version = Some(version);
+        }
+    }
+
+    fn sync_rid(&self, id: u32) -> u64 {
+        self.inner().get(&id).map_or(0, |it| it.sync_rid)
+    }
+
+    /// 合并 sync/maindata 增量数据，返回合并后的所有 torrent
+    fn merge_sync(&self, id: u32, data: MainData) -> HashMap<String, SyncTorrent> {
+        let mut inner = self.inner();
+        let Some(session) = inner.get_mut(&id) else {
+            return data.torrents;
+        };
+        if data.full_update {
+            session.sync_torrents.clear();
+        }
+        for (hash, torrent) in data.torrents {
+            session
+                .sync_torrents
+                .entry(hash)
+                .or_default()
+                .merge(torrent);
+        }
+        for hash in data.torrents_removed {
+            session.sync_torrents.remove(&hash);
+        }
+        session.sync_rid = data.rid;
+        session.sync_torrents.clone()
+    }
+}
 
 /// qbittorrent client
 /// [技术规范](https://github.com/qbittorrent/qBittorrent/wiki/WebUI-API-(qBittorrent-4.1))
 pub(crate) struct QB {
-    _id: u32,
+    id: u32,
+    client: Client,
     url: String,
     username: Option<String>,
     password: Option<String>,
     download_dir: String,
+    category: String,
 }
 
 impl QB {
+    pub(crate) fn id(&self) -> u32 {
+        self.id
+    }
+
     pub(crate) fn download_dir(&self) -> &str {
         self.download_dir.as_str()
     }
 
     pub(crate) async fn connect_test(&self) -> Result<()> {
-        self.app_version().await
+        // 连接测试时刷新 WebAPI 版本，以便 qbittorrent 升级后使用新的接口
+        let version = self.webapi_version().await?;
+        Session.set_version(self.id, version);
+        Ok(())
+    }
+
+    /// 获取 WebAPI 版本，优先使用缓存
+    async fn api_version(&self) -> Result<ApiVersion> {
+        if let Some(version) = Session.version(self.id) {
+            return Ok(version);
+        }
+        let version = self.webapi_version().await?;
+        Session.set_version(self.id, version);
+        Ok(version)
     }
 
     pub(crate) async fn download_list(&self) -> Result<Vec<DownloadItem>> {
@@ -36,21 +165,111 @@ impl QB {
         Ok(list.collect())
     }
 
+    /// 通过 sync/maindata 增量获取下载项状态
+    pub(crate) async fn item_states(&self) -> Result<HashMap<String, ItemState>> {
+        let data = self.sync_maindata(Session.sync_rid(self.id)).await?;
+        let torrents = Session.merge_sync(self.id, data).into_iter();
+        let torrents = torrents.filter(|(_, it)| self.match_category(it));
+        let states = torrents.map(|(hash, it)| (hash.clone(), (hash, it).into()));
+        Ok(states.collect())
+    }
+
+    /// 是否为当前分类的下载项，分类为空时匹配所有下载项
+    fn match_category(&self, torrent: &SyncTorrent) -> bool {
+        self.category.is_empty() || torrent.category.as_deref() == Some(self.category.as_str())
+    }
+
     pub(crate) async fn download_files(&mut self, id: &str) -> Result<Vec<String>> {
         let list = self.torrent_files(id).await?;
         let list = list.into_iter().map(|it| it.name);
         Ok(list.collect())
     }
+
+    pub(crate) async fn pause(&self, id: &str) -> Result<()> {
+        match self.api_version().await? >= ApiVersion::STOP_START {
+            true => self.torrents_action("stop", id).await,
+            false => self.torrents_action("pause", id).await,
+        }
+    }
+
+    pub(crate) async fn resume(&self, id: &str) -> Result<()> {
+        match self.api_version().await? >= ApiVersion::STOP_START {
+            true => self.torrents_action("start", id).await,
+            false => self.torrents_action("resume", id).await,
+        }
+    }
+
+    pub(crate) async fn rename_file(&self, id: &str, old_path: &str, new_path: &str) -> Result<()> {
+        if self.api_version().await? >= ApiVersion::RENAME_PATH {
+            return self.rename_file_by_path(id, old_path, new_path).await;
+        }
+        // 旧版本使用文件下标进行改名
+        let files = self.torrent_files(id).await?;
+        let index = files.iter().position(|it| it.name == old_path);
+        let index = index.with_context(|| format!("Can't find file `{old_path}`"))?;
+        self.rename_file_by_index(id, index, new_path).await
+    }
+
+    pub(crate) async fn rename_folder(
+        &self,
+        id: &str,
+        old_path: &str,
+        new_path: &str,
+    ) -> Result<()> {
+        if self.api_version().await? >= ApiVersion::RENAME_PATH {
+            return self.rename_folder_by_path(id, old_path, new_path).await;
+        }
+        // 旧版本不支持文件夹改名，逐个修改文件夹下的文件
+        let old_prefix = format!("{}/", old_path.trim_end_matches('/'));
+        let new_prefix = format!("{}/", new_path.trim_end_matches('/'));
+        let files = self.torrent_files(id).await?;
+        for (index, file) in files.iter().enumerate() {
+            if let Some(rest) = file.name.strip_prefix(&old_prefix) {
+                let new_name = format!("{new_prefix}{rest}");
+                self.rename_file_by_index(id, index, &new_name).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn set_files_wanted(&self, id: &str, wanted: &[usize]) -> Result<()> {
+        let count = self.torrent_files(id).await?.len();
+        let unwanted = (0..count).filter(|it| !wanted.contains(it));
+        let unwanted = unwanted.collect::<Vec<_>>();
+        // 文件优先级 0 为不下载，1 为正常
+        if !wanted.is_empty() {
+            self.file_prio(id, wanted, 1).await?;
+        }
+        if !unwanted.is_empty() {
+            self.file_prio(id, &unwanted, 0).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn set_seed_ratio_limit(&self, id: &str, ratio: Option<f64>) -> Result<()> {
+        // 设置时需要同时提交所有限制，先获取现有的限制
+        let mut limits = self.share_limits(id).await?;
+        limits.ratio_limit = ratio.unwrap_or(ShareLimits::UNLIMITED as f64);
+        self.set_share_limits(id, limits).await
+    }
+
+    pub(crate) async fn set_seed_time_limit(&self, id: &str, minutes: Option<u32>) -> Result<()> {
+        let mut limits = self.share_limits(id).await?;
+        limits.seeding_time_limit = minutes.map_or(ShareLimits::UNLIMITED, i64::from);
+        self.set_share_limits(id, limits).await
+    }
 }
 
 impl From<Downloader> for QB {
     fn from(value: Downloader) -> Self {
         Self {
-            _id: value.id,
+            id: value.id,
+            client: Session.client(value.id, &value.url),
             url: value.url,
             username: value.username,
             password: value.password,
             download_dir: value.download_dir,
+            category: value.category,
         }
     }
 }
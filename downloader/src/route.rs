@@ -0,0 +1,207 @@
+use anyhow::{bail, Result};
+
+use anitors::Element;
+use database::entity::{DownloadRule, Downloader, Torrent};
+
+use crate::{DownloadClient, DownloadSource};
+
+/// 路由选择的下载器
+pub struct RoutedClient {
+    /// 下载客户端
+    pub client: DownloadClient,
+    /// 下载基础路径
+    pub download_dir: String,
+}
+
+/// 路由匹配所需的 torrent 信息
+struct RouteInfo {
+    indexer_id: Option<u32>,
+    tmdb_id: Option<i64>,
+    is_movie: bool,
+    release_group: Option<String>,
+}
+
+impl RouteInfo {
+    fn new(torrent: &Torrent) -> Self {
+        Self {
+            indexer_id: torrent.indexer_id,
+            tmdb_id: torrent.tmdb_id,
+            is_movie: torrent.is_movie,
+            release_group: Element::parse(&torrent.name).release_group,
+        }
+    }
+
+    /// 规则中不为空的条件需要全部匹配
+    fn is_matched(&self, rule: &DownloadRule) -> bool {
+        let release_group = rule.release_group.as_deref().filter(|it| !it.is_empty());
+        let release_group = release_group.map(str::to_lowercase);
+        let self_release_group = self.release_group.as_deref().map(str::to_lowercase);
+        is_matched(rule.indexer_id, self.indexer_id)
+            && is_matched(rule.tmdb_id, self.tmdb_id)
+            && is_matched(rule.is_movie, Some(self.is_movie))
+            && is_matched(release_group, self_release_group)
+    }
+}
+
+/// 条件为空时匹配任意值
+fn is_matched<T: PartialEq>(condition: Option<T>, value: Option<T>) -> bool {
+    condition.is_none() || condition == value
+}
+
+/// 按优先级排列的候选下载器及下载基础路径
+///
+/// 先为匹配规则指定的下载器，之后为其余未被规则选中的下载器，用于规则下载器均不可用时回退
+fn candidates(
+    info: &RouteInfo,
+    rules: Vec<DownloadRule>,
+    downloaders: Vec<Downloader>,
+) -> Vec<(Downloader, Option<String>)> {
+    let rules = rules.into_iter().filter(|it| info.is_matched(it));
+    let mut candidates = rules
+        .filter_map(|rule| {
+            let downloader = downloaders.iter().find(|it| it.id == rule.downloader_id);
+            let download_dir = Some(rule.download_dir).filter(|it| !it.is_empty());
+            downloader.map(|it| (it.clone(), download_dir))
+        })
+        .collect::<Vec<_>>();
+    let rest = downloaders
+        .into_iter()
+        .filter(|it| candidates.iter().all(|(d, _)| d.id != it.id))
+        .collect::<Vec<_>>();
+    candidates.extend(rest.into_iter().map(|it| (it, None)));
+    candidates
+}
+
+impl DownloadClient {
+    /// 根据下载路由规则选择下载器及下载基础路径
+    ///
+    /// 按优先级依次尝试匹配的规则，下载器连接测试失败时尝试下一个规则；
+    /// 匹配规则的下载器均不可用或没有规则匹配时，依次尝试其余下载器
+    pub async fn route(torrent: &Torrent) -> Result<RoutedClient> {
+        let info = RouteInfo::new(torrent);
+        let downloaders = Downloader::find_all().await?;
+        let rules = DownloadRule::find_all_enable().await?;
+
+        for (downloader, download_dir) in candidates(&info, rules, downloaders) {
+            let name = downloader.name.clone();
+            let mut client = DownloadClient::from(downloader);
+            if let Err(e) = client.connect_test().await {
+                log::debug!("{e:#?}");
+                log::warn!("downloader `{name}` is unavailable, try next one: {e}");
+                continue;
+            }
+            let download_dir = download_dir.unwrap_or_else(|| client.download_dir().to_owned());
+            return Ok(RoutedClient {
+                client,
+                download_dir,
+            });
+        }
+
+        bail!("no available downloader for `{}`", torrent.name)
+    }
+
+    /// 通过下载路由选择下载器并添加 torrent，返回使用的下载器 id
+    pub async fn download_torrent(torrent: &Torrent) -> Result<u32> {
+        let RoutedClient {
+            mut client,
+            download_dir,
+        } = Self::route(torrent).await?;
+        let source = DownloadSource::from_url(&torrent.download_url);
+        client.download(source, &download_dir).await?;
+        Ok(client.id())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use database::entity::DownloaderType;
+
+    use super::*;
+
+    fn downloader(id: u32) -> Downloader {
+        Downloader {
+            id,
+            cat: DownloaderType::Qbittorrent,
+            name: format!("d{id}"),
+            url: String::default(),
+            username: None,
+            password: None,
+            download_dir: format!("/d{id}"),
+            category: String::default(),
+        }
+    }
+
+    fn rule(downloader_id: u32, download_dir: &str) -> DownloadRule {
+        DownloadRule {
+            id: 0,
+            name: String::default(),
+            priority: 0,
+            downloader_id,
+            download_dir: download_dir.into(),
+            indexer_id: None,
+            tmdb_id: None,
+            is_movie: None,
+            release_group: None,
+            enable: true,
+        }
+    }
+
+    fn info(is_movie: bool, release_group: Option<&str>) -> RouteInfo {
+        RouteInfo {
+            indexer_id: Some(1),
+            tmdb_id: Some(100),
+            is_movie,
+            release_group: release_group.map(Into::into),
+        }
+    }
+
+    fn ids(candidates: &[(Downloader, Option<String>)]) -> Vec<(u32, Option<&str>)> {
+        let ids = candidates.iter().map(|(d, dir)| (d.id, dir.as_deref()));
+        ids.collect()
+    }
+
+    #[test]
+    fn test_rule_matched() {
+        let info = info(true, Some("ANi"));
+        assert!(info.is_matched(&rule(1, "")));
+        let movie = DownloadRule {
+            is_movie: Some(true),
+            release_group: Some("ani".into()),
+            ..rule(1, "")
+        };
+        assert!(info.is_matched(&movie));
+        let tv = DownloadRule {
+            is_movie: Some(false),
+            ..rule(1, "")
+        };
+        assert!(!info.is_matched(&tv));
+        let indexer = DownloadRule {
+            indexer_id: Some(2),
+            ..rule(1, "")
+        };
+        assert!(!info.is_matched(&indexer));
+    }
+
+    #[test]
+    fn test_candidates() {
+        let downloaders = vec![downloader(1), downloader(2), downloader(3)];
+        let movie = DownloadRule {
+            is_movie: Some(true),
+            ..rule(2, "/movie")
+        };
+        let tv = DownloadRule {
+            is_movie: Some(false),
+            ..rule(3, "")
+        };
+        let rules = vec![movie, tv];
+
+        // 规则按顺序排在前面，其余下载器作为回退
+        let list = candidates(&info(true, None), rules.clone(), downloaders.clone());
+        assert_eq!(ids(&list), [(2, Some("/movie")), (1, None), (3, None)]);
+        let list = candidates(&info(false, None), rules, downloaders.clone());
+        assert_eq!(ids(&list), [(3, None), (1, None), (2, None)]);
+        // 没有规则时按下载器顺序
+        let list = candidates(&info(false, None), Vec::new(), downloaders);
+        assert_eq!(ids(&list), [(1, None), (2, None), (3, None)]);
+    }
+}
@@ -1,9 +1,11 @@
 use anyhow::{bail, ensure, Context, Result};
 use reqwest::{RequestBuilder, Response as Resp, StatusCode};
-use serde::de::DeserializeOwned;
+use serde::de::{DeserializeOwned, IgnoredAny};
 
 use encode::base64_encode;
 
+use crate::DownloadSource;
+
 use super::receiver::{AddTorrentResp, PortTestResp, Response, TorrentInfo, TorrentList};
 use super::sender::Request;
 use super::{Session, CLIENT, TR};
@@ -16,8 +18,20 @@ impl TR {
         Ok(())
     }
 
-    pub(super) async fn add_torrent(&self, torrent: &[u8], dir: &str) -> Result<String> {
-        let req = self.build_req(Request::torrent_add(base64_encode(torrent), dir));
+    pub(super) async fn add_torrent(
+        &self,
+        source: DownloadSource<'_>,
+        dir: &str,
+    ) -> Result<String> {
+        let request = match source {
+            DownloadSource::Torrent(torrent) => {
+                Request::torrent_add(base64_encode(torrent), dir, &self.category)
+            }
+            DownloadSource::Magnet(url) | DownloadSource::Url(url) => {
+                Request::torrent_add_url(url, dir, &self.category)
+            }
+        };
+        let req = self.build_req(request);
         let resp: AddTorrentResp = self.rpc(req).await?;
         Ok(resp.into_hash_string())
     }
@@ -30,10 +44,28 @@ impl TR {
         let list = resp
             .torrents
             .into_iter()
-            .filter(TorrentInfo::match_category);
+            .filter(|it| it.match_category(&self.category));
         Ok(list.collect())
     }
 
+    pub(super) async fn torrent_action(&self, method: &'static str, id: &str) -> Result<()> {
+        let req = self.build_req(Request::torrent_action(method, id));
+        let _: IgnoredAny = self.rpc(req).await?;
+        Ok(())
+    }
+
+    pub(super) async fn torrent_remove(&self, id: &str, delete_local_data: bool) -> Result<()> {
+        let req = self.build_req(Request::torrent_remove(id, delete_local_data));
+        let _: IgnoredAny = self.rpc(req).await?;
+        Ok(())
+    }
+
+    pub(super) async fn torrent_set(&self, request: Request<'_>) -> Result<()> {
+        let req = self.build_req(request);
+        let _: IgnoredAny = self.rpc(req).await?;
+        Ok(())
+    }
+
     pub(super) async fn torrent_info(&self, id: &str) -> Result<TorrentInfo> {
         let req = self.build_req(Request::torrent_info(id));
         let resp: TorrentList = self.rpc(req).await?;
@@ -69,6 +101,14 @@ impl TR {
     }
 
     async fn rpc<R: DeserializeOwned>(&self, req: RequestBuilder) -> Result<R> {
+        let resp = self.try_rpc(req).await;
+        if resp.is_err() {
+            crate::rpc_failed("transmission");
+        }
+        resp
+    }
+
+    async fn try_rpc<R: DeserializeOwned>(&self, req: RequestBuilder) -> Result<R> {
         // 未使用 stream body，clone 不会失败
         let first_req = req.try_clone().unwrap();
         let resp = first_req.send().await?;
@@ -7,7 +7,9 @@ use reqwest::Client;
 
 use database::entity::Downloader;
 
-use crate::DownloadItem;
+use crate::{DownloadItem, DownloadSource, ItemPriority};
+
+use sender::Request;
 
 mod handler;
 mod receiver;
@@ -15,7 +17,7 @@ mod sender;
 
 static CLIENT: LazyLock<Client> = LazyLock::new(Client::default);
 
-struct Session;
+pub(crate) struct Session;
 
 impl Session {
     fn inner(&self) -> MutexGuard<'static, HashMap<u32, Arc<str>>> {
@@ -30,6 +32,10 @@ impl Session {
     fn set(&self, id: u32, session: &str) {
         self.inner().insert(id, session.into());
     }
+
+    pub(crate) fn remove(&self, id: u32) {
+        self.inner().remove(&id);
+    }
 }
 
 /// transmission client
@@ -40,9 +46,14 @@ pub(crate) struct TR {
     username: Option<String>,
     password: Option<String>,
     download_dir: String,
+    category: String,
 }
 
 impl TR {
+    pub(crate) fn id(&self) -> u32 {
+        self.id
+    }
+
     pub(crate) fn download_dir(&self) -> &str {
         self.download_dir.as_str()
     }
@@ -51,8 +62,8 @@ impl TR {
         self.port_test().await
     }
 
-    pub(crate) async fn download(&self, torrent: &[u8], dir: &str) -> Result<()> {
-        self.add_torrent(torrent, dir).await.map(|_| ())
+    pub(crate) async fn download(&self, source: DownloadSource<'_>, dir: &str) -> Result<()> {
+        self.add_torrent(source, dir).await.map(|_| ())
     }
 
     pub(crate) async fn download_list(&self) -> Result<Vec<DownloadItem>> {
@@ -66,6 +77,46 @@ impl TR {
         let item: DownloadItem = info.into();
         Ok(item.relative_path)
     }
+
+    pub(crate) async fn pause(&self, id: &str) -> Result<()> {
+        self.torrent_action("torrent-stop", id).await
+    }
+
+    pub(crate) async fn resume(&self, id: &str) -> Result<()> {
+        self.torrent_action("torrent-start", id).await
+    }
+
+    pub(crate) async fn remove(&self, id: &str, delete_data: bool) -> Result<()> {
+        self.torrent_remove(id, delete_data).await
+    }
+
+    pub(crate) async fn recheck(&self, id: &str) -> Result<()> {
+        self.torrent_action("torrent-verify", id).await
+    }
+
+    pub(crate) async fn set_priority(&self, id: &str, priority: ItemPriority) -> Result<()> {
+        match priority {
+            ItemPriority::High => self.torrent_action("queue-move-top", id).await,
+            ItemPriority::Low => self.torrent_action("queue-move-bottom", id).await,
+        }
+    }
+
+    pub(crate) async fn set_files_wanted(&self, id: &str, wanted: &[usize]) -> Result<()> {
+        let count = self.torrent_info(id).await?.file_count();
+        let unwanted = (0..count).filter(|it| !wanted.contains(it));
+        let request = Request::torrent_set_files(id, wanted.to_vec(), unwanted.collect());
+        self.torrent_set(request).await
+    }
+
+    pub(crate) async fn set_seed_ratio_limit(&self, id: &str, ratio: Option<f64>) -> Result<()> {
+        self.torrent_set(Request::torrent_set_seed_ratio(id, ratio))
+            .await
+    }
+
+    pub(crate) async fn set_seed_time_limit(&self, id: &str, minutes: Option<u32>) -> Result<()> {
+        self.torrent_set(Request::torrent_set_seed_idle(id, minutes))
+            .await
+    }
 }
 
 impl From<Downloader> for TR {
@@ -76,6 +127,7 @@ impl From<Downloader> for TR {
             username: value.username,
             password: value.password,
             download_dir: value.download_dir,
+            category: value.category,
         }
     }
 }
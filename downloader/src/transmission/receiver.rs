@@ -1,6 +1,6 @@
 use serde::Deserialize;
 
-use crate::{DownloadItem, ItemStatus, DEFAULT_CATEGORY};
+use crate::{DownloadItem, ItemStatus};
 
 #[derive(Debug, Deserialize)]
 pub(super) struct Response<T> {
@@ -54,16 +54,28 @@ pub(super) struct TorrentInfo {
     is_finished: bool,
     status: u8,
     labels: Vec<String>,
+    download_dir: String,
+    size_when_done: u64,
+    left_until_done: u64,
+    rate_download: u64,
+    rate_upload: u64,
+    upload_ratio: f64,
+    eta: i64,
+    peers_connected: u32,
+    added_date: i64,
+    done_date: i64,
     #[serde(default)]
     files: Vec<FileInfo>,
 }
 
 impl TorrentInfo {
-    pub(super) fn match_category(&self) -> bool {
-        self.labels
-            .iter()
-            .find(|it| it.as_str() == DEFAULT_CATEGORY)
-            .is_some()
+    /// 是否为指定分类（标签），分类为空时匹配所有 torrent
+    pub(super) fn match_category(&self, category: &str) -> bool {
+        category.is_empty() || self.labels.iter().any(|it| it == category)
+    }
+
+    pub(super) fn file_count(&self) -> usize {
+        self.files.len()
     }
 }
 
@@ -96,6 +108,20 @@ impl From<TorrentInfo> for DownloadItem {
             info_hash: value.hash_string,
             status,
             relative_path: paths.collect(),
+            save_path: value.download_dir,
+            local_path: String::default(),
+            size: value.size_when_done,
+            downloaded: value.size_when_done.saturating_sub(value.left_until_done),
+            progress: value.percent_done,
+            download_speed: value.rate_download,
+            upload_speed: value.rate_upload,
+            // 分享率 -1 为不可用，-2 为无限
+            ratio: value.upload_ratio.max(0.0),
+            // eta -1 为不可用，-2 为未知
+            eta: u64::try_from(value.eta).ok(),
+            peers: value.peers_connected,
+            added_at: Some(value.added_date).filter(|it| *it > 0),
+            completed_at: Some(value.done_date).filter(|it| *it > 0),
         }
     }
 }
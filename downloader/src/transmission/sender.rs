@@ -1,32 +1,96 @@
 use serde::Serialize;
 
-use crate::DEFAULT_CATEGORY;
-
-static TORRENT_FIELDS: [&str; 6] = [
+static TORRENT_FIELDS: [&str; 16] = [
     "id",
     "hashString",
     "percentDone",
     "isFinished",
     "status",
     "labels",
+    "downloadDir",
+    "sizeWhenDone",
+    "leftUntilDone",
+    "rateDownload",
+    "rateUpload",
+    "uploadRatio",
+    "eta",
+    "peersConnected",
+    "addedDate",
+    "doneDate",
 ];
 
-static DETAIL_FIELDS: [&str; 7] = [
+static DETAIL_FIELDS: [&str; 17] = [
     "id",
     "hashString",
     "percentDone",
     "isFinished",
     "status",
     "labels",
+    "downloadDir",
+    "sizeWhenDone",
+    "leftUntilDone",
+    "rateDownload",
+    "rateUpload",
+    "uploadRatio",
+    "eta",
+    "peersConnected",
+    "addedDate",
+    "doneDate",
     "files",
 ];
 
+/// torrent id，transmission 仅将数字识别为 id，字符串识别为 hash
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum TorrentId<'a> {
+    Id(i64),
+    Hash(&'a str),
+}
+
+impl<'a> From<&'a str> for TorrentId<'a> {
+    fn from(value: &'a str) -> Self {
+        match value.parse() {
+            Ok(id) => Self::Id(id),
+            Err(_) => Self::Hash(value),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(untagged, rename_all = "kebab-case")]
 enum RequestArg<'a> {
     Empty,
+    Torrent {
+        ids: [TorrentId<'a>; 1],
+    },
+    RemoveTorrent {
+        ids: [TorrentId<'a>; 1],
+        #[serde(rename = "delete-local-data")]
+        delete_local_data: bool,
+    },
+    SetFilesWanted {
+        ids: [TorrentId<'a>; 1],
+        #[serde(rename = "files-wanted")]
+        files_wanted: Vec<usize>,
+        #[serde(rename = "files-unwanted")]
+        files_unwanted: Vec<usize>,
+    },
+    SetSeedRatio {
+        ids: [TorrentId<'a>; 1],
+        #[serde(rename = "seedRatioLimit", skip_serializing_if = "Option::is_none")]
+        seed_ratio_limit: Option<f64>,
+        #[serde(rename = "seedRatioMode")]
+        seed_ratio_mode: u8,
+    },
+    SetSeedIdle {
+        ids: [TorrentId<'a>; 1],
+        #[serde(rename = "seedIdleLimit", skip_serializing_if = "Option::is_none")]
+        seed_idle_limit: Option<u32>,
+        #[serde(rename = "seedIdleMode")]
+        seed_idle_mode: u8,
+    },
     GetTorrent {
-        ids: [&'a str; 1],
+        ids: [TorrentId<'a>; 1],
         fields: &'static [&'static str],
     },
     GetTorrentList {
@@ -34,11 +98,26 @@ enum RequestArg<'a> {
     },
     AddTorrent {
         metainfo: String,
+        #[serde(rename = "download-dir")]
+        download_dir: &'a str,
+        labels: Vec<&'a str>,
+    },
+    AddTorrentUrl {
+        filename: &'a str,
+        #[serde(rename = "download-dir")]
         download_dir: &'a str,
-        labels: [&'a str; 1],
+        labels: Vec<&'a str>,
     },
 }
 
+/// 下载分类为空时不添加标签
+fn labels(label: &str) -> Vec<&str> {
+    Some(label)
+        .filter(|it| !it.is_empty())
+        .into_iter()
+        .collect()
+}
+
 /// 跳过 arg 序列化检查
 fn skip_arguments(arg: &RequestArg) -> bool {
     matches!(arg, RequestArg::Empty)
@@ -60,13 +139,29 @@ impl<'a> Request<'a> {
         }
     }
 
-    pub(super) fn torrent_add(metainfo: String, download_dir: &'a str) -> Self {
+    pub(super) fn torrent_add(metainfo: String, download_dir: &'a str, label: &'a str) -> Self {
         Self {
             method: "torrent-add",
             arguments: RequestArg::AddTorrent {
                 metainfo,
                 download_dir,
-                labels: [DEFAULT_CATEGORY],
+                labels: labels(label),
+            },
+        }
+    }
+
+    /// 通过 magnet 链接或 torrent 文件 url 添加
+    pub(super) fn torrent_add_url(
+        filename: &'a str,
+        download_dir: &'a str,
+        label: &'a str,
+    ) -> Self {
+        Self {
+            method: "torrent-add",
+            arguments: RequestArg::AddTorrentUrl {
+                filename,
+                download_dir,
+                labels: labels(label),
             },
         }
     }
@@ -84,9 +179,97 @@ impl<'a> Request<'a> {
         Self {
             method: "torrent-get",
             arguments: RequestArg::GetTorrent {
-                ids: [id],
+                ids: [id.into()],
                 fields: &DETAIL_FIELDS,
             },
         }
     }
+
+    /// 仅需 id 参数的 torrent 操作，如 torrent-start、torrent-stop 等
+    pub(super) fn torrent_action(method: &'static str, id: &'a str) -> Self {
+        Self {
+            method,
+            arguments: RequestArg::Torrent { ids: [id.into()] },
+        }
+    }
+
+    pub(super) fn torrent_remove(id: &'a str, delete_local_data: bool) -> Self {
+        Self {
+            method: "torrent-remove",
+            arguments: RequestArg::RemoveTorrent {
+                ids: [id.into()],
+                delete_local_data,
+            },
+        }
+    }
+
+    pub(super) fn torrent_set_files(id: &'a str, wanted: Vec<usize>, unwanted: Vec<usize>) -> Self {
+        Self {
+            method: "torrent-set",
+            arguments: RequestArg::SetFilesWanted {
+                ids: [id.into()],
+                files_wanted: wanted,
+                files_unwanted: unwanted,
+            },
+        }
+    }
+
+    /// 分享率限制，mode 1 为单独设置，2 为不限制
+    pub(super) fn torrent_set_seed_ratio(id: &'a str, ratio: Option<f64>) -> Self {
+        Self {
+            method: "torrent-set",
+            arguments: RequestArg::SetSeedRatio {
+                ids: [id.into()],
+                seed_ratio_limit: ratio,
+                seed_ratio_mode: if ratio.is_some() { 1 } else { 2 },
+            },
+        }
+    }
+
+    /// 空闲做种时间限制，mode 1 为单独设置，2 为不限制
+    pub(super) fn torrent_set_seed_idle(id: &'a str, minutes: Option<u32>) -> Self {
+        Self {
+            method: "torrent-set",
+            arguments: RequestArg::SetSeedIdle {
+                ids: [id.into()],
+                seed_idle_limit: minutes,
+                seed_idle_mode: if minutes.is_some() { 1 } else { 2 },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, to_value};
+
+    use super::*;
+
+    #[test]
+    fn test_torrent_add() {
+        let req = Request::torrent_add("bWV0YQ==".into(), "/d", "anime");
+        let expected = json!({
+            "method": "torrent-add",
+            "arguments": { "metainfo": "bWV0YQ==", "download-dir": "/d", "labels": ["anime"] },
+        });
+        assert_eq!(to_value(req).unwrap(), expected);
+
+        let req = Request::torrent_add_url("magnet:?xt=urn:btih:x", "/d", "");
+        let expected = json!({
+            "method": "torrent-add",
+            "arguments": { "filename": "magnet:?xt=urn:btih:x", "download-dir": "/d", "labels": [] },
+        });
+        assert_eq!(to_value(req).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_torrent_id() {
+        let req = Request::torrent_action("torrent-start", "70000");
+        let value = to_value(req).unwrap();
+        assert_eq!(value["arguments"], json!({ "ids": [70000] }));
+
+        let hash = "c12fe1c06bba254a9dc9f519b335aa7c1367a88a";
+        let value = to_value(Request::torrent_action("torrent-start", hash)).unwrap();
+        assert_eq!(value["arguments"], json!({ "ids": [hash] }));
+    }
 }
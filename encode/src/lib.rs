@@ -12,3 +12,37 @@ pub fn base64_encode(data: impl AsRef<[u8]>) -> String {
     use base64::Engine;
     base64::engine::general_purpose::STANDARD.encode(data)
 }
+
+/// 使用 Argon2id 计算密码 hash，结果为包含盐值及参数的 PHC 字符串
+///
+/// Argon2 计算耗时较长，异步环境中需要在阻塞线程中调用
+pub fn password_hash(password: impl AsRef<[u8]>) -> String {
+    use argon2::password_hash::rand_core::OsRng;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = argon2::Argon2::default().hash_password(password.as_ref(), &salt);
+    // 默认参数及随机盐值不会导致计算失败
+    hash.expect("argon2 hash failed").to_string()
+}
+
+/// 校验密码，兼容旧版本未加盐的 SHA-256 hash
+///
+/// Argon2 计算耗时较长，异步环境中需要在阻塞线程中调用
+pub fn password_verify(password: impl AsRef<[u8]>, hash: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+    use subtle::ConstantTimeEq;
+    if password_need_rehash(hash) {
+        let encoded = sha256_encode(password);
+        return encoded.as_bytes().ct_eq(hash.as_bytes()).into();
+    }
+    let Ok(hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+    let argon2 = argon2::Argon2::default();
+    argon2.verify_password(password.as_ref(), &hash).is_ok()
+}
+
+/// 是否为旧版本的 hash，需要在校验成功后重新计算
+pub fn password_need_rehash(hash: &str) -> bool {
+    !hash.starts_with('$')
+}
@@ -0,0 +1,128 @@
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+/// 事件缓冲数量，订阅者处理过慢时丢弃旧事件
+const CAPACITY: usize = 1024;
+
+/// 应用事件，用于 web 页面实时更新
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum Event {
+    /// 新增 torrent
+    TorrentIndexed {
+        indexer_id: Option<u32>,
+        id: String,
+        name: String,
+        title: String,
+        /// 解析出的 tmdb id，为空时表示未匹配到影片
+        tmdb_id: Option<i64>,
+        season: String,
+        episode: String,
+    },
+    /// torrent 解析失败
+    ParseFailed { name: String, error: String },
+    /// 索引器拉取完成
+    IndexerFetched {
+        indexer_id: u32,
+        name: String,
+        fetched: usize,
+        inserted: usize,
+    },
+    /// 索引器拉取失败
+    IndexerFetchFailed {
+        indexer_id: u32,
+        name: String,
+        error: String,
+    },
+    /// 下载状态变化
+    Download(DownloadEvent),
+    /// 设置已修改，key 为修改的设置项
+    SettingChanged { key: String },
+}
+
+impl Event {
+    /// 事件名称，与序列化后的 type 字段相同
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::TorrentIndexed { .. } => "torrent_indexed",
+            Event::ParseFailed { .. } => "parse_failed",
+            Event::IndexerFetched { .. } => "indexer_fetched",
+            Event::IndexerFetchFailed { .. } => "indexer_fetch_failed",
+            Event::Download(_) => "download",
+            Event::SettingChanged { .. } => "setting_changed",
+        }
+    }
+}
+
+/// 下载事件，id 为下载器中的下载项 id，info_hash 为 torrent hash(v1)
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DownloadEvent {
+    /// 添加下载项
+    Added {
+        downloader_id: u32,
+        id: String,
+        info_hash: String,
+    },
+    /// 下载进度变化
+    Progress {
+        downloader_id: u32,
+        id: String,
+        info_hash: String,
+        progress: f64,
+        download_speed: u64,
+    },
+    /// 下载完成
+    Completed {
+        downloader_id: u32,
+        id: String,
+        info_hash: String,
+    },
+    /// 做种完成
+    SeedingFinished {
+        downloader_id: u32,
+        id: String,
+        info_hash: String,
+    },
+    /// 下载出错
+    Errored {
+        downloader_id: u32,
+        id: String,
+        info_hash: String,
+    },
+    /// 移除下载项
+    Removed {
+        downloader_id: u32,
+        id: String,
+        info_hash: String,
+    },
+    /// 重命名下载项中的文件或目录
+    Renamed {
+        downloader_id: u32,
+        id: String,
+        info_hash: String,
+        old_path: String,
+        new_path: String,
+    },
+}
+
+/// 事件总线，各模块发布事件，web 服务订阅后推送给页面
+pub struct EventBus;
+
+impl EventBus {
+    fn sender(&self) -> &'static Sender<Event> {
+        static SENDER: OnceLock<Sender<Event>> = OnceLock::new();
+        SENDER.get_or_init(|| broadcast::channel(CAPACITY).0)
+    }
+
+    /// 发布事件，没有订阅者时直接丢弃
+    pub fn publish(&self, event: Event) {
+        _ = self.sender().send(event);
+    }
+
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.sender().subscribe()
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+use super::CLIENT;
+
+/// Bark iOS 推送
+#[derive(Deserialize)]
+pub(super) struct Bark {
+    /// 服务地址，自建服务时需要修改
+    #[serde(default = "Bark::default_server")]
+    server: String,
+    device_key: String,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    sound: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    device_key: &'a str,
+    title: &'a str,
+    body: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sound: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    code: i32,
+    #[serde(default)]
+    message: String,
+}
+
+impl Bark {
+    fn default_server() -> String {
+        "https://api.day.app".into()
+    }
+
+    pub(super) async fn send(&self, message: &Message) -> Result<()> {
+        let url = format!("{}/push", self.server.trim_end_matches('/'));
+        let req = Request {
+            device_key: &self.device_key,
+            title: &message.title,
+            body: &message.body,
+            group: self.group.as_deref(),
+            sound: self.sound.as_deref(),
+        };
+        let resp = CLIENT.post(url).json(&req).send().await?;
+        let resp: Response = resp.error_for_status()?.json().await?;
+        ensure!(resp.code == 200, "bark push failed: {}", resp.message);
+        Ok(())
+    }
+}
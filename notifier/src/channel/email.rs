@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message as Mail, Tokio1Executor};
+use serde::Deserialize;
+
+use crate::Message;
+
+/// SMTP 连接加密方式
+#[derive(Copy, Clone, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Security {
+    /// 不加密，仅用于本地测试
+    None,
+    #[default]
+    Starttls,
+    Tls,
+}
+
+/// SMTP 邮件
+#[derive(Deserialize)]
+pub(super) struct Email {
+    host: String,
+    /// 端口，为空时使用加密方式的默认端口
+    #[serde(default)]
+    port: Option<u16>,
+    #[serde(default)]
+    security: Security,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    from: String,
+    to: Vec<String>,
+}
+
+impl Email {
+    pub(super) fn validate(&self) -> Result<()> {
+        self.mail(&Message::test())?;
+        Ok(())
+    }
+
+    fn mail(&self, message: &Message) -> Result<Mail> {
+        let from: Mailbox = self.from.parse().context("invalid email sender")?;
+        let mut mail = Mail::builder().from(from).subject(&message.title);
+        for to in &self.to {
+            mail = mail.to(to.parse().context("invalid email recipient")?);
+        }
+        let mail = mail.header(ContentType::TEXT_PLAIN);
+        Ok(mail.body(message.body.clone())?)
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let mut builder = match self.security {
+            Security::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host),
+            Security::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)?,
+            Security::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)?,
+        };
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = self.username.as_deref().filter(|it| !it.is_empty()) {
+            let password = self.password.clone().unwrap_or_default();
+            builder = builder.credentials(Credentials::new(username.into(), password));
+        }
+        Ok(builder.build())
+    }
+
+    pub(super) async fn send(&self, message: &Message) -> Result<()> {
+        self.transport()?.send(self.mail(message)?).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy as LazyLock;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+
+use database::entity::{Notification, NotificationChannel};
+
+use crate::Message;
+
+use bark::Bark;
+use email::Email;
+use server_chan::ServerChan;
+use sonarr::Sonarr;
+use telegram::Telegram;
+use webhook::Webhook;
+
+mod bark;
+mod email;
+mod server_chan;
+mod sonarr;
+mod telegram;
+mod webhook;
+
+static CLIENT: LazyLock<Client> = LazyLock::new(|| {
+    let client = Client::builder().timeout(Duration::from_secs(30));
+    client.build().unwrap()
+});
+
+fn config<T: DeserializeOwned>(notification: &Notification) -> Result<T> {
+    serde_json::from_value(notification.config.clone()).context("invalid notification config")
+}
+
+pub(crate) fn validate(notification: &Notification) -> Result<()> {
+    match notification.channel {
+        NotificationChannel::Webhook => config::<Webhook>(notification)?.validate()?,
+        NotificationChannel::Telegram => drop(config::<Telegram>(notification)?),
+        NotificationChannel::Bark => drop(config::<Bark>(notification)?),
+        NotificationChannel::ServerChan => drop(config::<ServerChan>(notification)?),
+        NotificationChannel::Email => config::<Email>(notification)?.validate()?,
+        NotificationChannel::Sonarr => drop(config::<Sonarr>(notification)?),
+    }
+    Ok(())
+}
+
+pub(crate) async fn send(notification: &Notification, message: &Message) -> Result<()> {
+    match notification.channel {
+        NotificationChannel::Webhook => config::<Webhook>(notification)?.send(message).await,
+        NotificationChannel::Telegram => config::<Telegram>(notification)?.send(message).await,
+        NotificationChannel::Bark => config::<Bark>(notification)?.send(message).await,
+        NotificationChannel::ServerChan => config::<ServerChan>(notification)?.send(message).await,
+        NotificationChannel::Email => config::<Email>(notification)?.send(message).await,
+        NotificationChannel::Sonarr => config::<Sonarr>(notification)?.send(message).await,
+    }
+}
@@ -0,0 +1,37 @@
+use anyhow::{ensure, Result};
+use serde::Deserialize;
+
+use crate::Message;
+
+use super::CLIENT;
+
+/// Server 酱（Turbo 版）
+#[derive(Deserialize)]
+pub(super) struct ServerChan {
+    #[serde(default = "ServerChan::default_api_url")]
+    api_url: String,
+    send_key: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    code: i32,
+    #[serde(default)]
+    message: String,
+}
+
+impl ServerChan {
+    fn default_api_url() -> String {
+        "https://sctapi.ftqq.com".into()
+    }
+
+    pub(super) async fn send(&self, message: &Message) -> Result<()> {
+        let api_url = self.api_url.trim_end_matches('/');
+        let url = format!("{api_url}/{}.send", self.send_key);
+        let form = [("title", &message.title), ("desp", &message.body)];
+        let resp = CLIENT.post(url).form(&form).send().await?;
+        let resp: Response = resp.error_for_status()?.json().await?;
+        ensure!(resp.code == 0, "server chan push failed: {}", resp.message);
+        Ok(())
+    }
+}
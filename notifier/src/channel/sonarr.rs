@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use anitors::Element;
+use database::entity::{Downloader, DownloaderType, Indexer, Torrent};
+use downloader::DownloadClient;
+
+use crate::{EventKind, Message};
+
+use super::CLIENT;
+
+/// 与 Sonarr/Radarr 的 Grab、Download 事件格式相同的 webhook，
+/// 电影使用 Radarr 格式，其他使用 Sonarr 格式
+///
+/// 仅发送下载开始（Grab）及下载完成（Download）事件
+#[derive(Deserialize)]
+pub(super) struct Sonarr {
+    url: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default = "Sonarr::default_instance_name")]
+    instance_name: String,
+    #[serde(default)]
+    application_url: String,
+}
+
+impl Sonarr {
+    fn default_instance_name() -> String {
+        "mikanarr".into()
+    }
+
+    pub(super) async fn send(&self, message: &Message) -> Result<()> {
+        let event_type = match message.kind {
+            EventKind::DownloadStarted => "Grab",
+            EventKind::DownloadCompleted => "Download",
+            EventKind::Test => "Test",
+            _ => return Ok(()),
+        };
+        let torrent = match message.kind {
+            EventKind::Test => test_torrent(),
+            _ => match &message.torrent {
+                Some(torrent) => torrent.clone(),
+                // 手动添加到下载器的下载项不在 torrent 表中，跳过
+                None => return Ok(()),
+            },
+        };
+        let downloader = match message.downloader_id {
+            Some(id) => Downloader::find_by_id(id).await?,
+            None => None,
+        };
+        let indexer = match torrent.indexer_id {
+            Some(id) => Indexer::find_by_id(id).await?.map(|it| it.name),
+            None => None,
+        };
+        let file = match (event_type, &downloader) {
+            ("Download", Some(downloader)) => {
+                let file = DownloadFile::find(downloader, &torrent.id).await?;
+                Some(file.context("download item not found")?)
+            }
+            _ => None,
+        };
+        let payload = self.payload(
+            event_type,
+            &torrent,
+            indexer,
+            downloader.as_ref(),
+            file.as_ref(),
+        );
+
+        let mut req = CLIENT.post(&self.url);
+        for (key, value) in &self.headers {
+            req = req.header(key, value);
+        }
+        req.json(&payload).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    fn payload(
+        &self,
+        event_type: &str,
+        torrent: &Torrent,
+        indexer: Option<String>,
+        downloader: Option<&Downloader>,
+        file: Option<&DownloadFile>,
+    ) -> Value {
+        let element = Element::parse(&torrent.name);
+        let year = torrent.year.parse::<i32>().unwrap_or_default();
+        let tmdb_id = torrent.tmdb_id.unwrap_or_default();
+
+        let mut payload = match torrent.is_movie {
+            true => json!({
+                "movie": {
+                    "id": 0,
+                    "title": torrent.title,
+                    "year": year,
+                    "folderPath": "",
+                    "tmdbId": tmdb_id,
+                    "imdbId": torrent.imdb_id,
+                },
+                "remoteMovie": {
+                    "tmdbId": tmdb_id,
+                    "imdbId": torrent.imdb_id,
+                    "title": torrent.title,
+                    "year": year,
+                },
+            }),
+            false => json!({
+                "series": {
+                    "id": 0,
+                    "title": torrent.title,
+                    "path": "",
+                    "tvdbId": torrent.tvdb_id.unwrap_or_default(),
+                    "tvMazeId": 0,
+                    "tmdbId": tmdb_id,
+                    "imdbId": torrent.imdb_id,
+                    "type": "anime",
+                    "year": year,
+                },
+                "episodes": [{
+                    "id": 0,
+                    "episodeNumber": torrent.episode.parse::<i32>().unwrap_or_default(),
+                    "seasonNumber": torrent.season.parse::<i32>().unwrap_or_default(),
+                    "title": "",
+                    "seriesId": 0,
+                }],
+            }),
+        };
+
+        let quality = element.video_resolution.unwrap_or_default();
+        let release_group = element.release_group.unwrap_or_default();
+        match event_type {
+            "Download" => {
+                let file = json!({
+                    "id": 0,
+                    "relativePath": file.map_or(&torrent.name, |it| &it.relative_path),
+                    "path": file.map(|it| &it.path),
+                    "quality": quality,
+                    "releaseGroup": release_group,
+                    "sceneName": torrent.name,
+                    "size": file.map_or(0, |it| it.size),
+                });
+                let key = if torrent.is_movie {
+                    "movieFile"
+                } else {
+                    "episodeFile"
+                };
+                payload[key] = file;
+                payload["isUpgrade"] = false.into();
+            }
+            _ => {
+                payload["release"] = json!({
+                    "quality": quality,
+                    "qualityVersion": 1,
+                    "releaseGroup": release_group,
+                    "releaseTitle": torrent.name,
+                    "indexer": indexer.unwrap_or_default(),
+                    "size": 0,
+                });
+            }
+        }
+
+        if let Some(downloader) = downloader {
+            payload["downloadClient"] = downloader.name.as_str().into();
+            payload["downloadClientType"] = client_type(downloader.cat).into();
+        }
+        payload["downloadId"] = torrent.id.to_uppercase().into();
+        payload["eventType"] = event_type.into();
+        payload["instanceName"] = self.instance_name.as_str().into();
+        payload["applicationUrl"] = self.application_url.as_str().into();
+        payload
+    }
+}
+
+/// 下载完成的文件，路径取自下载器中的下载项
+struct DownloadFile {
+    /// 相对于保存路径的文件路径
+    relative_path: String,
+    /// 经过路径映射后的本地完整路径
+    path: PathBuf,
+    size: u64,
+}
+
+impl DownloadFile {
+    /// 在下载器中按 hash 查找下载项，下载项已被移除时返回 `None`
+    async fn find(downloader: &Downloader, info_hash: &str) -> Result<Option<Self>> {
+        let mut client = DownloadClient::from(downloader.clone());
+        let list = client.download_list().await?;
+        let item = list
+            .into_iter()
+            .find(|it| it.info_hash.eq_ignore_ascii_case(info_hash));
+        let Some(item) = item else {
+            return Ok(None);
+        };
+        let files = client.download_files(&item.id).await?;
+        let relative_path = main_file(&files).cloned().unwrap_or_default();
+        Ok(Some(Self {
+            path: Path::new(&item.local_path).join(&relative_path),
+            relative_path,
+            size: item.size,
+        }))
+    }
+}
+
+/// 多个文件时优先使用视频文件
+fn main_file(files: &[String]) -> Option<&String> {
+    const VIDEO_EXTENSIONS: [&str; 5] = ["mkv", "mp4", "avi", "ts", "webm"];
+    let is_video = |file: &&String| {
+        let extension = Path::new(file.as_str()).extension();
+        let extension = extension.and_then(|it| it.to_str()).unwrap_or_default();
+        VIDEO_EXTENSIONS
+            .iter()
+            .any(|it| it.eq_ignore_ascii_case(extension))
+    };
+    files.iter().find(is_video).or(files.first())
+}
+
+/// 与 Sonarr 中的下载器类型名称保持一致
+fn client_type(cat: DownloaderType) -> &'static str {
+    match cat {
+        DownloaderType::Aira2 => "Aria2",
+        DownloaderType::Qbittorrent => "qBittorrent",
+        DownloaderType::Transmission => "Transmission",
+        DownloaderType::Embedded => "mikanarr",
+    }
+}
+
+/// 与 Sonarr 测试事件相同，使用固定的测试数据
+fn test_torrent() -> Torrent {
+    Torrent {
+        name: "Test.Title.S01E01.1080p".into(),
+        title: "Test Title".into(),
+        season: "1".into(),
+        episode: "1".into(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sonarr() -> Sonarr {
+        Sonarr {
+            url: String::new(),
+            headers: HashMap::new(),
+            instance_name: Sonarr::default_instance_name(),
+            application_url: String::new(),
+        }
+    }
+
+    fn downloader() -> Downloader {
+        Downloader {
+            id: 1,
+            cat: DownloaderType::Aira2,
+            name: "aria2".into(),
+            url: String::new(),
+            username: None,
+            password: None,
+            download_dir: "/downloads".into(),
+            category: "mikanarr".into(),
+        }
+    }
+
+    fn file() -> DownloadFile {
+        DownloadFile {
+            relative_path: "Test/Test.S01E01.mkv".into(),
+            path: PathBuf::from("/media/mikanarr/Test/Test.S01E01.mkv"),
+            size: 1024,
+        }
+    }
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<_> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    fn payload(event_type: &str, torrent: &Torrent) -> Value {
+        let file = file();
+        let file = (event_type == "Download").then_some(&file);
+        let indexer = Some("mikan".into());
+        sonarr().payload(event_type, torrent, indexer, Some(&downloader()), file)
+    }
+
+    const COMMON_KEYS: [&str; 6] = [
+        "applicationUrl",
+        "downloadClient",
+        "downloadClientType",
+        "downloadId",
+        "eventType",
+        "instanceName",
+    ];
+
+    fn expected_keys(keys: &[&'static str]) -> Vec<&'static str> {
+        let mut keys = [&COMMON_KEYS[..], keys].concat();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_sonarr_payload() {
+        let torrent = Torrent {
+            id: "c12fe1c06bba254a9dc9f519b335aa7c1367a88a".into(),
+            ..test_torrent()
+        };
+
+        let grab = payload("Grab", &torrent);
+        assert_eq!(
+            keys(&grab),
+            expected_keys(&["episodes", "release", "series"])
+        );
+        assert_eq!(
+            keys(&grab["series"]),
+            ["id", "imdbId", "path", "title", "tmdbId", "tvMazeId", "tvdbId", "type", "year"]
+        );
+        assert_eq!(
+            keys(&grab["episodes"][0]),
+            ["episodeNumber", "id", "seasonNumber", "seriesId", "title"]
+        );
+        assert_eq!(
+            keys(&grab["release"]),
+            [
+                "indexer",
+                "quality",
+                "qualityVersion",
+                "releaseGroup",
+                "releaseTitle",
+                "size"
+            ]
+        );
+        assert_eq!(grab["eventType"], "Grab");
+        assert_eq!(grab["downloadClientType"], "Aria2");
+        assert_eq!(
+            grab["downloadId"],
+            "C12FE1C06BBA254A9DC9F519B335AA7C1367A88A"
+        );
+        assert_eq!(grab["release"]["indexer"], "mikan");
+
+        let download = payload("Download", &torrent);
+        assert_eq!(
+            keys(&download),
+            expected_keys(&["episodeFile", "episodes", "isUpgrade", "series"])
+        );
+        assert_eq!(
+            keys(&download["episodeFile"]),
+            [
+                "id",
+                "path",
+                "quality",
+                "relativePath",
+                "releaseGroup",
+                "sceneName",
+                "size"
+            ]
+        );
+        assert_eq!(
+            download["episodeFile"]["path"],
+            "/media/mikanarr/Test/Test.S01E01.mkv"
+        );
+        assert_eq!(
+            download["episodeFile"]["relativePath"],
+            "Test/Test.S01E01.mkv"
+        );
+        assert_eq!(download["episodeFile"]["size"], 1024);
+    }
+
+    #[test]
+    fn test_radarr_payload() {
+        let torrent = Torrent {
+            is_movie: true,
+            ..test_torrent()
+        };
+
+        let grab = payload("Grab", &torrent);
+        assert_eq!(
+            keys(&grab),
+            expected_keys(&["movie", "release", "remoteMovie"])
+        );
+        assert_eq!(
+            keys(&grab["movie"]),
+            ["folderPath", "id", "imdbId", "title", "tmdbId", "year"]
+        );
+        assert_eq!(
+            keys(&grab["remoteMovie"]),
+            ["imdbId", "title", "tmdbId", "year"]
+        );
+
+        let download = payload("Download", &torrent);
+        assert_eq!(
+            keys(&download),
+            expected_keys(&["isUpgrade", "movie", "movieFile", "remoteMovie"])
+        );
+        assert_eq!(
+            download["movieFile"]["path"],
+            "/media/mikanarr/Test/Test.S01E01.mkv"
+        );
+    }
+
+    #[test]
+    fn test_main_file() {
+        let files = ["Test/cover.jpg".to_string(), "Test/Test.S01E01.MKV".into()];
+        assert_eq!(main_file(&files).unwrap(), "Test/Test.S01E01.MKV");
+        let files = ["Test.S01E01.ass".to_string()];
+        assert_eq!(main_file(&files).unwrap(), "Test.S01E01.ass");
+        assert!(main_file(&[]).is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::Message;
+
+use super::CLIENT;
+
+/// Telegram Bot API
+#[derive(Deserialize)]
+pub(super) struct Telegram {
+    /// api 地址，可以替换为反向代理地址
+    #[serde(default = "Telegram::default_api_url")]
+    api_url: String,
+    bot_token: String,
+    /// 用户、群组 id 或频道名（@channel）
+    chat_id: String,
+}
+
+impl Telegram {
+    fn default_api_url() -> String {
+        "https://api.telegram.org".into()
+    }
+
+    pub(super) async fn send(&self, message: &Message) -> Result<()> {
+        let api_url = self.api_url.trim_end_matches('/');
+        let url = format!("{api_url}/bot{}/sendMessage", self.bot_token);
+        let body = json!({ "chat_id": self.chat_id, "text": message.text() });
+        let resp = CLIENT.post(url).json(&body).send().await?;
+        resp.error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// 使用本地服务代替 Telegram api，检查请求路径及内容
+    #[tokio::test]
+    async fn test_send_to_local_api() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let len = stream.read(&mut buf).await.unwrap();
+            let resp = "HTTP/1.1 200 OK\r\ncontent-length: 11\r\n\r\n{\"ok\":true}";
+            stream.write_all(resp.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buf[..len]).to_string()
+        });
+
+        let telegram = Telegram {
+            api_url,
+            bot_token: "token".into(),
+            chat_id: "42".into(),
+        };
+        telegram.send(&Message::test()).await.unwrap();
+        let req = server.await.unwrap();
+        assert!(req.starts_with("POST /bottoken/sendMessage "));
+        assert!(req.contains(r#""chat_id":"42""#));
+    }
+}
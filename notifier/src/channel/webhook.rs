@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::Message;
+
+use super::CLIENT;
+
+/// 通用 webhook，请求体为 JSON
+#[derive(Deserialize)]
+pub(super) struct Webhook {
+    url: String,
+    /// 请求方法，默认为 POST
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    /// JSON 请求体模版，支持 `{{event}}`、`{{title}}`、`{{body}}` 占位符，
+    /// 占位符需要写在 JSON 字符串中；为空时发送完整的消息
+    #[serde(default)]
+    template: Option<String>,
+}
+
+impl Webhook {
+    pub(super) fn validate(&self) -> Result<()> {
+        self.method()?;
+        self.payload(&Message::test())?;
+        Ok(())
+    }
+
+    fn method(&self) -> Result<Method> {
+        let method = self.method.as_deref().unwrap_or("POST");
+        Method::from_bytes(method.to_uppercase().as_bytes()).context("invalid webhook method")
+    }
+
+    fn payload(&self, message: &Message) -> Result<Value> {
+        let Some(template) = self.template.as_deref().filter(|it| !it.is_empty()) else {
+            return Ok(serde_json::to_value(message)?);
+        };
+        let event = serde_json::to_value(message.kind)?;
+        let payload = template
+            .replace("{{event}}", &escape(event.as_str().unwrap_or_default()))
+            .replace("{{title}}", &escape(&message.title))
+            .replace("{{body}}", &escape(&message.body));
+        serde_json::from_str(&payload).context("invalid webhook template")
+    }
+
+    pub(super) async fn send(&self, message: &Message) -> Result<()> {
+        let mut req = CLIENT.request(self.method()?, &self.url);
+        for (key, value) in &self.headers {
+            req = req.header(key, value);
+        }
+        let resp = req.json(&self.payload(message)?).send().await?;
+        resp.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// 转义为 JSON 字符串的内容（不含两侧引号）
+fn escape(value: &str) -> String {
+    let value = Value::String(value.into()).to_string();
+    value[1..value.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_template_payload() {
+        let webhook = Webhook {
+            url: String::new(),
+            method: None,
+            headers: HashMap::new(),
+            template: Some(r#"{"text": "{{title}}: {{body}}", "type": "{{event}}"}"#.into()),
+        };
+        let mut message = Message::test();
+        message.body = "line \"1\"\nline 2".into();
+        let payload = webhook.payload(&message).unwrap();
+        let text = format!("{}: {}", message.title, message.body);
+        assert_eq!(payload["text"], text.as_str());
+        assert_eq!(payload["type"], "test");
+    }
+}
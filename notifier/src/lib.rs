@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use database::entity::Notification;
+use eventbus::{Event, EventBus};
+
+pub use message::{EventKind, Message};
+
+mod channel;
+mod message;
+
+/// 订阅事件总线并发送通知，token 取消后等待发送中的通知完成再结束
+pub async fn run(token: CancellationToken) {
+    let tracker = TaskTracker::new();
+    receive(&token, &tracker).await;
+    tracker.close();
+    tracker.wait().await;
+}
+
+async fn receive(token: &CancellationToken, tracker: &TaskTracker) {
+    let mut receiver = EventBus.subscribe();
+    // 索引器持续不可用时仅在首次失败时通知
+    let mut down_indexers = HashSet::new();
+    loop {
+        let event = tokio::select! {
+            it = receiver.recv() => it,
+            _ = token.cancelled() => return,
+        };
+        let event = match event {
+            Ok(it) => it,
+            Err(RecvError::Lagged(count)) => {
+                log::warn!("notifier lagged, {count} events skipped");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        match &event {
+            Event::IndexerFetched { indexer_id, .. } => {
+                down_indexers.remove(indexer_id);
+            }
+            Event::IndexerFetchFailed { indexer_id, .. } if !down_indexers.insert(*indexer_id) => {
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(message) = Message::from_event(&event).await {
+            dispatch(tracker, message).await;
+        }
+    }
+}
+
+/// 发送到所有订阅了该事件的通知渠道，各渠道并发发送
+async fn dispatch(tracker: &TaskTracker, message: Message) {
+    let notifications = match Notification::find_all_enable().await {
+        Ok(it) => it,
+        Err(e) => return log::warn!("load notifications failed: {e}"),
+    };
+    for notification in notifications {
+        if !is_subscribed(&notification, message.kind) {
+            continue;
+        }
+        let message = message.clone();
+        tracker.spawn(async move {
+            if let Err(e) = channel::send(&notification, &message).await {
+                log::debug!("{e:#?}");
+                log::warn!("send notification `{}` failed: {e}", notification.name);
+            }
+        });
+    }
+}
+
+/// 事件列表为空时订阅所有事件
+fn is_subscribed(notification: &Notification, kind: EventKind) -> bool {
+    match event_kinds(notification) {
+        Ok(kinds) => kinds.is_empty() || kinds.contains(&kind),
+        Err(_) => false,
+    }
+}
+
+fn event_kinds(notification: &Notification) -> Result<Vec<EventKind>> {
+    serde_json::from_value(notification.events.clone()).context("invalid notification events")
+}
+
+/// 校验通知的事件列表及渠道配置，添加或修改前调用
+pub fn validate(notification: &Notification) -> Result<()> {
+    event_kinds(notification)?;
+    channel::validate(notification)
+}
+
+/// 发送测试消息
+pub async fn send_test(notification: &Notification) -> Result<()> {
+    validate(notification)?;
+    channel::send(notification, &Message::test()).await
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use database::entity::Torrent;
+use eventbus::{DownloadEvent, Event};
+
+/// 可以订阅的通知事件
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// 新增的 torrent 匹配到了影片剧集
+    EpisodeMatched,
+    DownloadStarted,
+    DownloadCompleted,
+    DownloadRenamed,
+    DownloadFailed,
+    /// 索引器拉取失败，恢复前仅通知一次
+    IndexerDown,
+    /// 测试消息，不需要订阅
+    Test,
+}
+
+/// 通知消息
+#[derive(Clone, Debug, Serialize)]
+pub struct Message {
+    #[serde(rename = "event")]
+    pub kind: EventKind,
+    pub title: String,
+    pub body: String,
+    /// 原始事件
+    pub data: Value,
+    /// 事件相关的 torrent 信息
+    #[serde(skip)]
+    pub(crate) torrent: Option<Torrent>,
+    /// 下载事件的下载器 id
+    #[serde(skip)]
+    pub(crate) downloader_id: Option<u32>,
+}
+
+impl Message {
+    /// 不需要通知的事件返回 `None`
+    pub(crate) async fn from_event(event: &Event) -> Option<Self> {
+        let mut torrent = None;
+        let mut downloader_id = None;
+        if let Event::Download(event) = event {
+            // 不需要通知的下载事件，避免频繁的进度事件查询数据库
+            if matches!(
+                event,
+                DownloadEvent::Progress { .. }
+                    | DownloadEvent::SeedingFinished { .. }
+                    | DownloadEvent::Removed { .. }
+            ) {
+                return None;
+            }
+            let (id, hash) = download_id(event);
+            downloader_id = Some(id);
+            torrent = Torrent::find_by_id(hash).await.ok();
+        }
+        // 下载事件中仅有下载项 id 及 hash，尽量使用数据库中的 torrent 名称
+        let name = torrent.as_ref().map(|it| it.name.clone());
+        let (kind, title, body) = match event {
+            Event::TorrentIndexed {
+                name,
+                title,
+                tmdb_id,
+                season,
+                episode,
+                ..
+            } => {
+                if tmdb_id.is_none() || episode.is_empty() {
+                    return None;
+                }
+                let title = format!("New episode: {title} S{season}E{episode}");
+                (EventKind::EpisodeMatched, title, name.clone())
+            }
+            Event::IndexerFetchFailed { name, error, .. } => {
+                let title = format!("Indexer `{name}` is down");
+                (EventKind::IndexerDown, title, error.clone())
+            }
+            Event::Download(event) => match event {
+                DownloadEvent::Added { id, .. } => {
+                    let body = name.unwrap_or_else(|| id.clone());
+                    (EventKind::DownloadStarted, "Download started".into(), body)
+                }
+                DownloadEvent::Completed { id, .. } => {
+                    let body = name.unwrap_or_else(|| id.clone());
+                    (
+                        EventKind::DownloadCompleted,
+                        "Download completed".into(),
+                        body,
+                    )
+                }
+                DownloadEvent::Errored { id, .. } => {
+                    let body = name.unwrap_or_else(|| id.clone());
+                    (EventKind::DownloadFailed, "Download failed".into(), body)
+                }
+                DownloadEvent::Renamed {
+                    old_path, new_path, ..
+                } => {
+                    let body = format!("{old_path} -> {new_path}");
+                    (EventKind::DownloadRenamed, "Download renamed".into(), body)
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            title,
+            body,
+            data: serde_json::to_value(event).unwrap_or_default(),
+            torrent,
+            downloader_id,
+        })
+    }
+
+    pub(crate) fn test() -> Self {
+        Self {
+            kind: EventKind::Test,
+            title: "mikanarr test notification".into(),
+            body: "If you see this message, the notification is working.".into(),
+            data: Value::Null,
+            torrent: None,
+            downloader_id: None,
+        }
+    }
+
+    /// 标题及内容合并后的纯文本
+    pub(crate) fn text(&self) -> String {
+        format!("{}\n{}", self.title, self.body)
+    }
+}
+
+/// 下载事件的下载器 id 及 torrent hash
+fn download_id(event: &DownloadEvent) -> (u32, &str) {
+    match event {
+        DownloadEvent::Added {
+            downloader_id,
+            info_hash,
+            ..
+        }
+        | DownloadEvent::Progress {
+            downloader_id,
+            info_hash,
+            ..
+        }
+        | DownloadEvent::Completed {
+            downloader_id,
+            info_hash,
+            ..
+        }
+        | DownloadEvent::SeedingFinished {
+            downloader_id,
+            info_hash,
+            ..
+        }
+        | DownloadEvent::Errored {
+            downloader_id,
+            info_hash,
+            ..
+        }
+        | DownloadEvent::Removed {
+            downloader_id,
+            info_hash,
+            ..
+        }
+        | DownloadEvent::Renamed {
+            downloader_id,
+            info_hash,
+            ..
+        } => (*downloader_id, info_hash),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_download_id() {
+        // aria2 的下载项 id 为 gid，需要使用 hash 查询 torrent
+        let event = DownloadEvent::Completed {
+            downloader_id: 1,
+            id: "2089b05ecca3d829".into(),
+            info_hash: "c12fe1c06bba254a9dc9f519b335aa7c1367a88a".into(),
+        };
+        assert_eq!(
+            download_id(&event),
+            (1, "c12fe1c06bba254a9dc9f519b335aa7c1367a88a")
+        );
+    }
+}
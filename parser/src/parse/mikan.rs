@@ -21,6 +21,7 @@ pub(super) async fn mikan_direct_mapping(torrent: &mut Torrent) {
         Err(e) => {
             log::debug!("{e:#?}");
             log::warn!("{e}");
+            super::parse_failed("mikan");
             return;
         }
     };
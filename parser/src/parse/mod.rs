@@ -1,6 +1,7 @@
 use std::future::Future;
 
 use anyhow::Result;
+use metrics::counter;
 
 use database::entity::Torrent;
 
@@ -18,7 +19,11 @@ pub trait ParseTorrent {
 
 impl ParseTorrent for Torrent {
     async fn try_parse_hash(&mut self) -> Result<()> {
-        self.id = info_hash::parse_url_hash(&self.download_url).await?;
+        let hash = info_hash::parse_url_hash(&self.download_url).await;
+        if hash.is_err() {
+            parse_failed("hash");
+        }
+        self.id = hash?;
         Ok(())
     }
 
@@ -28,7 +33,15 @@ impl ParseTorrent for Torrent {
         // 本地文件名解析处理
         name_info::name_local_parse(self);
         // 附加 tmdb 的准确信息
-        tmdb_ids::append_extra_ids(self).await?;
-        Ok(())
+        let result = tmdb_ids::append_extra_ids(self).await;
+        if result.is_err() {
+            parse_failed("tmdb");
+        }
+        result
     }
 }
+
+/// 记录解析失败次数，stage 为 hash、mikan、local_name 或 tmdb
+fn parse_failed(stage: &'static str) {
+    counter!("mikanarr_parse_failures_total", "stage" => stage).increment(1);
+}
@@ -7,6 +7,9 @@ pub(super) fn name_local_parse(torrent: &mut Torrent) {
 
     if torrent.title.is_empty() {
         torrent.title = element.anime_title.unwrap_or_default();
+        if torrent.title.is_empty() {
+            super::parse_failed("local_name");
+        }
     }
     if torrent.season.is_empty() {
         torrent.season = element.anime_season.unwrap_or_default();
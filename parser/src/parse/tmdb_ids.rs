@@ -1,4 +1,7 @@
+use std::time::Instant;
+
 use anyhow::Result;
+use metrics::{counter, histogram};
 use once_cell::sync::Lazy as LazyLock;
 
 use database::entity::Torrent;
@@ -23,7 +26,10 @@ pub(super) async fn append_extra_ids(torrent: &mut Torrent) -> Result<()> {
 async fn search_by_title(torrent: &mut Torrent) -> Result<()> {
     // 之前标题解析成功时查询
     if !torrent.title.is_empty() {
-        let req = TMDB.search_tv(&torrent.title).execute().await?;
+        let start = Instant::now();
+        let req = TMDB.search_tv(&torrent.title).execute().await;
+        record_request("search_tv", start, &req);
+        let req = req?;
 
         match req.results.into_iter().next() {
             None => log::info!(
@@ -38,12 +44,15 @@ async fn search_by_title(torrent: &mut Torrent) -> Result<()> {
 }
 
 async fn search_extra_ids(tmdb_id: i64, torrent: &mut Torrent) -> Result<()> {
+    let start = Instant::now();
     let req = TMDB
         .tv_series_detail(tmdb_id)
         .append_to_response("external_ids")
         .language(Language::ZhCn)
         .execute()
-        .await?;
+        .await;
+    record_request("tv_series_detail", start, &req);
+    let req = req?;
 
     torrent.title = req.name;
     torrent.tvdb_id = req.external_ids.tvdb_id;
@@ -51,3 +60,12 @@ async fn search_extra_ids(tmdb_id: i64, torrent: &mut Torrent) -> Result<()> {
 
     Ok(())
 }
+
+/// 记录 tmdb 请求次数及耗时
+fn record_request<T>(endpoint: &'static str, start: Instant, result: &Result<T>) {
+    let status = if result.is_ok() { "success" } else { "error" };
+    let labels = [("endpoint", endpoint), ("status", status)];
+    counter!("mikanarr_tmdb_requests_total", &labels).increment(1);
+    histogram!("mikanarr_tmdb_request_duration_seconds", "endpoint" => endpoint)
+        .record(start.elapsed());
+}
@@ -1,9 +1,7 @@
-pub use server::search;
+pub use server::{
+    connect_test, fetch_and_save_torrents, last_fetch_time, refresh_indexers, search,
+    search_torrents, TorrentQuery,
+};
 
 mod rss;
 mod server;
-
-/// 加载后台服务
-pub fn load() {
-    let _ = tokio::spawn(server::fetch_and_save_torrents());
-}
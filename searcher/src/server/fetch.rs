@@ -1,60 +1,154 @@
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
 use bytes::Bytes;
+use metrics::{counter, histogram};
 use once_cell::sync::Lazy as LazyLock;
 use reqwest::Client;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 
 use database::entity::{Indexer, IndexerCategory, Torrent};
+use eventbus::{Event, EventBus};
 use parser::ParseTorrent;
 
 use crate::rss::{parse_torrent_rss, parse_torznab_rss};
 
-/// 循环拉取并保存 torrent
-pub(crate) async fn fetch_and_save_torrents() {
-    loop {
-        let indexers = Indexer::find_all_enable().await.unwrap_or_else(|e| {
+/// 最后一次完成拉取的时间（unix 时间戳，秒），0 表示尚未完成过拉取
+static LAST_FETCH: AtomicU64 = AtomicU64::new(0);
+
+/// 最后一次完成拉取所有索引器的时间（unix 时间戳，秒）
+pub fn last_fetch_time() -> Option<u64> {
+    let time = LAST_FETCH.load(Ordering::Relaxed);
+    (time != 0).then_some(time)
+}
+
+/// 循环拉取并保存 torrent，token 取消后在当前 torrent 保存完成后结束
+pub async fn fetch_and_save_torrents(token: CancellationToken) {
+    while !token.is_cancelled() {
+        let start = Instant::now();
+        if let Err(e) = refresh_indexers(&token).await {
             log::debug!("{e:#?}");
             log::warn!("get Indexer from database error, try again later: {e}");
-            Vec::default()
-        });
+        }
+        histogram!("mikanarr_fetch_loop_duration_seconds").record(start.elapsed());
+        tokio::select! {
+            _ = sleep(Duration::from_secs(10 * 60)) => {}
+            _ = token.cancelled() => {}
+        }
+    }
+}
 
-        for indexer in indexers {
-            let torrents = match indexer.category {
-                IndexerCategory::Rss => fetch_torrent_rss(&indexer.url).await,
-                IndexerCategory::Torznab => fetch_torznab(&indexer.url).await,
-            };
-            let torrents = match torrents {
-                Ok(it) => it,
-                Err(e) => {
-                    log::debug!("{e:#?}");
-                    log::warn!("fetch `{}` torrent error, skip it: {e}", indexer.name);
-                    continue;
+/// 拉取所有启用的索引器并保存 torrent，单个索引器出错时跳过
+pub async fn refresh_indexers(token: &CancellationToken) -> Result<()> {
+    for indexer in Indexer::find_all_enable().await? {
+        if token.is_cancelled() {
+            break;
+        }
+        let torrents = match indexer.category {
+            IndexerCategory::Rss => fetch_torrent_rss(&indexer.url).await,
+            IndexerCategory::Torznab => fetch_torznab(&indexer.url).await,
+        };
+        let labels = [("indexer", indexer.name.clone())];
+        let torrents = match torrents {
+            Ok(it) => it,
+            Err(e) => {
+                log::debug!("{e:#?}");
+                log::warn!("fetch `{}` torrent error, skip it: {e}", indexer.name);
+                counter!("mikanarr_indexer_fetch_errors_total", &labels).increment(1);
+                EventBus.publish(Event::IndexerFetchFailed {
+                    indexer_id: indexer.id,
+                    name: indexer.name,
+                    error: sanitize_error(&e),
+                });
+                continue;
+            }
+        };
+        let fetched = torrents.len();
+        counter!("mikanarr_torrents_fetched_total", &labels).increment(fetched as u64);
+        let counter = counter!("mikanarr_torrents_inserted_total", &labels);
+        let mut inserted = 0;
+        for mut torrent in torrents {
+            if token.is_cancelled() {
+                break;
+            }
+            torrent.indexer_id = Some(indexer.id);
+            let torrent_name = torrent.name.clone();
+            match parse_info_and_save(torrent).await {
+                Ok(true) => {
+                    counter.increment(1);
+                    inserted += 1;
                 }
-            };
-            for torrent in torrents {
-                let torrent_name = torrent.name.clone();
-                if let Err(e) = parse_info_and_save(torrent).await {
+                Ok(false) => {}
+                Err(e) => {
                     log::debug!("{e:#?}");
                     log::warn!("parse `{torrent_name}` torrent error, skip it: {e}");
+                    EventBus.publish(Event::ParseFailed {
+                        name: torrent_name,
+                        error: sanitize_error(&e),
+                    });
                 }
             }
         }
-
-        sleep(Duration::from_secs(10 * 60)).await;
+        EventBus.publish(Event::IndexerFetched {
+            indexer_id: indexer.id,
+            name: indexer.name,
+            fetched,
+            inserted,
+        });
+    }
+    if !token.is_cancelled() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        LAST_FETCH.store(now.as_secs(), Ordering::Relaxed);
     }
+    Ok(())
 }
 
-/// 解析 torrent 信息并存入数据库
-async fn parse_info_and_save(mut torrent: Torrent) -> Result<()> {
+/// 测试索引器是否可以访问，仅拉取并解析，不保存 torrent
+pub async fn connect_test(indexer: &Indexer) -> Result<()> {
+    match indexer.category {
+        IndexerCategory::Rss => fetch_torrent_rss(&indexer.url).await?,
+        IndexerCategory::Torznab => fetch_torznab(&indexer.url).await?,
+    };
+    Ok(())
+}
+
+/// 解析 torrent 信息并存入数据库，返回是否为新增的 torrent
+async fn parse_info_and_save(mut torrent: Torrent) -> Result<bool> {
     torrent.try_parse_hash().await?;
     // 重复 torrent 不再进行解析
-    if !torrent.exist().await {
-        torrent.try_parse_detail().await?;
-        torrent.insert().await?;
+    if torrent.exist().await {
+        return Ok(false);
+    }
+    torrent.try_parse_detail().await?;
+    let event = Event::TorrentIndexed {
+        indexer_id: torrent.indexer_id,
+        id: torrent.id.clone(),
+        name: torrent.name.clone(),
+        title: torrent.title.clone(),
+        tmdb_id: torrent.tmdb_id,
+        season: torrent.season.clone(),
+        episode: torrent.episode.clone(),
+    };
+    torrent.insert().await?;
+    EventBus.publish(event);
+    Ok(true)
+}
+
+/// 事件会发送到通知渠道，请求错误中的 URL 可能包含 api key 等凭据，仅保留协议及主机
+fn sanitize_error(e: &anyhow::Error) -> String {
+    let message = e.to_string();
+    let url = e
+        .chain()
+        .find_map(|it| it.downcast_ref::<reqwest::Error>()?.url());
+    match url {
+        Some(url) => {
+            let host = url.host_str().unwrap_or_default();
+            message.replace(url.as_str(), &format!("{}://{host}", url.scheme()))
+        }
+        None => message,
     }
-    Ok(())
 }
 
 /// 拉取 rss torrent 信息
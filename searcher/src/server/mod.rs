@@ -1,6 +1,10 @@
+use anyhow::Result;
 use poem::{get, Route};
 
-pub(crate) use fetch::fetch_and_save_torrents;
+use database::entity::{Torrent, TorrentSearch};
+use parser::ParseTorrent;
+
+pub use fetch::{connect_test, fetch_and_save_torrents, last_fetch_time, refresh_indexers};
 
 mod fetch;
 mod torznab;
@@ -9,3 +13,39 @@ mod torznab;
 pub fn search() -> Route {
     Route::new().nest("/torznab", get(torznab::handle))
 }
+
+/// torrent 查询条件
+#[derive(Default)]
+pub struct TorrentQuery {
+    /// 名称，用于解析影片信息
+    pub name: String,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<i64>,
+    pub season: Option<String>,
+}
+
+/// 解析查询条件后搜索数据库中的 torrent
+pub async fn search_torrents(query: TorrentQuery) -> Result<Vec<Torrent>> {
+    let mut torrent = Torrent {
+        name: query.name,
+        imdb_id: query.imdb_id.unwrap_or_default(),
+        tvdb_id: query.tvdb_id,
+        season: query.season.unwrap_or_default(),
+        ..Default::default()
+    };
+    torrent.try_parse_detail().await.ok();
+
+    let param = TorrentSearch {
+        imdb: match torrent.imdb_id.as_str() {
+            "" => None,
+            s => Some(s),
+        },
+        tvdb: torrent.tvdb_id,
+        se: match torrent.season.as_str() {
+            "" => None,
+            s => Some(s),
+        },
+    };
+
+    Torrent::filter(param).await
+}
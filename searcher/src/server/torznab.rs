@@ -1,14 +1,19 @@
+use std::time::Instant;
+
+use metrics::{counter, histogram};
 use poem::http::header::CONTENT_TYPE;
 use poem::http::HeaderValue;
 use poem::web::Query;
 use poem::{handler, IntoResponse, Response, Result as PoemResult};
 use serde::Deserialize;
+use subtle::ConstantTimeEq;
 
-use database::entity::{Torrent, TorrentSearch};
-use parser::ParseTorrent;
+use database::entity::{Config, Torrent};
 
 use crate::rss::new_torznab_rss;
 
+use super::{search_torrents, TorrentQuery};
+
 // language=XML
 static CAPS_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
 <caps>
@@ -27,11 +32,22 @@ static CAPS_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
   </categories>
 </caps>"#;
 
+// language=XML
+static API_KEY_ERROR_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<error code="100" description="Incorrect user credentials" />"#;
+
 #[handler]
 pub(super) async fn handle(Query(mut param): Query<SearchParam>) -> PoemResult<impl IntoResponse> {
+    let apikey = Config.torznab_apikey().await?;
+    if let Some(resp) = check_apikey(param.apikey.as_deref(), &apikey) {
+        return Ok(resp);
+    }
+
+    counter!("mikanarr_torznab_queries_total", "type" => param.function.name()).increment(1);
+
     // cap 请求直接返回
     if matches!(param.function, SearchType::Caps) {
-        return Ok(caps_resp());
+        return Ok(xml_resp(CAPS_XML));
     }
 
     // 不支持的 cat 查询，直接返回空信息，仅支持为空或者默认 cat 值 1000, 2000
@@ -45,7 +61,10 @@ pub(super) async fn handle(Query(mut param): Query<SearchParam>) -> PoemResult<i
     }
 
     // 查询并返回
-    Ok(channel_resp(database_search(&param).await?)?)
+    let start = Instant::now();
+    let torrents = database_search(&param).await;
+    histogram!("mikanarr_torznab_query_duration_seconds").record(start.elapsed());
+    Ok(channel_resp(torrents?)?)
 }
 
 #[derive(Deserialize)]
@@ -60,6 +79,17 @@ enum SearchType {
     MovieSearch,
 }
 
+impl SearchType {
+    fn name(&self) -> &'static str {
+        match self {
+            SearchType::Caps => "caps",
+            SearchType::Search => "search",
+            SearchType::TVSearch => "tvsearch",
+            SearchType::MovieSearch => "movie",
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct SearchParam {
     #[serde(rename = "t")]
@@ -73,6 +103,7 @@ struct SearchParam {
     #[serde(rename = "tvdbid")]
     tvdb_id: Option<String>,
     season: Option<String>,
+    apikey: Option<String>,
     /// 是否为电影，此参数作为处理其他参数时辅助参数
     #[serde(skip)]
     is_movie: Option<bool>,
@@ -107,31 +138,24 @@ fn is_matched_search_type(param: &mut SearchParam) -> bool {
 
 /// 数据库搜索
 async fn database_search(param: &SearchParam) -> anyhow::Result<Vec<Torrent>> {
-    let mut torrent = Torrent::default();
-    torrent.name = param.query.clone();
-    torrent.imdb_id = param.imdb_id.as_deref().unwrap_or_default().to_owned();
-    torrent.tvdb_id = param.tvdb_id.as_deref().and_then(|it| it.parse().ok());
-    torrent.season = param.season.as_deref().unwrap_or_default().to_owned();
-    torrent.try_parse_detail().await.ok();
-
-    let param = TorrentSearch {
-        imdb: match torrent.imdb_id.as_str() {
-            "" => None,
-            s => Some(s),
-        },
-        tvdb: torrent.tvdb_id,
-        se: match torrent.season.as_str() {
-            "" => None,
-            s => Some(s),
-        },
+    let query = TorrentQuery {
+        name: param.query.clone(),
+        imdb_id: param.imdb_id.clone(),
+        tvdb_id: param.tvdb_id.as_deref().and_then(|it| it.parse().ok()),
+        season: param.season.clone(),
     };
+    search_torrents(query).await
+}
 
-    Torrent::filter(param).await
+/// torznab caps 及错误响应
+/// apikey 错误时返回 torznab 规范中的错误码 100，使用常量时间比较
+fn check_apikey(param: Option<&str>, apikey: &str) -> Option<Response> {
+    let matched = param.is_some_and(|it| it.as_bytes().ct_eq(apikey.as_bytes()).into());
+    (!matched).then(|| xml_resp(API_KEY_ERROR_XML))
 }
 
-/// torznab caps 响应
-fn caps_resp() -> Response {
-    let mut resp = Response::from(CAPS_XML);
+fn xml_resp(xml: &'static str) -> Response {
+    let mut resp = Response::from(xml);
     let xml_type = HeaderValue::from_static("application/xml; charset=utf-8");
     resp.headers_mut().insert(CONTENT_TYPE, xml_type);
     resp
@@ -151,3 +175,26 @@ fn channel_resp(torrents: Vec<Torrent>) -> anyhow::Result<Response> {
     response.headers_mut().insert(CONTENT_TYPE, rss_type);
     Ok(response)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const APIKEY: &str = "0123456789abcdef0123456789abcdef";
+
+    #[tokio::test]
+    async fn test_apikey_error() {
+        for param in [None, Some(""), Some("wrong"), Some(&APIKEY[..31])] {
+            let resp = check_apikey(param, APIKEY).expect("apikey should be rejected");
+            let content_type = resp.headers().get(CONTENT_TYPE).unwrap();
+            assert_eq!(content_type, "application/xml; charset=utf-8");
+            let body = resp.into_body().into_string().await.unwrap();
+            assert!(body.contains(r#"<error code="100""#), "{body}");
+        }
+    }
+
+    #[test]
+    fn test_apikey_matched() {
+        assert!(check_apikey(Some(APIKEY), APIKEY).is_none());
+    }
+}
@@ -0,0 +1,238 @@
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+
+use anyhow::{ensure, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use tokio_util::sync::CancellationToken;
+
+use anitors::Element;
+use database::entity::{Indexer, IndexerCategory, IndexerSearch, Torrent, User};
+use parser::ParseTorrent;
+use searcher::TorrentQuery;
+
+use crate::config::ConfigArgs;
+
+/// mikanarr，命令行参数未指定时读取对应的环境变量
+#[derive(Parser)]
+#[command(version, about)]
+pub(crate) struct Cli {
+    #[command(flatten)]
+    pub(crate) config: ConfigArgs,
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum Command {
+    /// 启动 web 服务，未指定子命令时的默认操作
+    Serve,
+    #[command(flatten)]
+    Task(TaskCommand),
+}
+
+/// 除 serve 之外的子命令，执行后退出
+#[derive(Subcommand)]
+pub(crate) enum TaskCommand {
+    /// 重设用户密码，新密码从标准输入读取，避免出现在命令历史及进程列表中
+    ResetPassword {
+        /// 用户名，默认为第一个管理员
+        #[arg(long)]
+        username: Option<String>,
+    },
+    /// 索引器管理
+    #[command(subcommand)]
+    Indexer(IndexerCommand),
+    /// torrent 管理
+    #[command(subcommand)]
+    Torrent(TorrentCommand),
+    /// 数据库维护
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// 解析文件名，输出 anitors 及 parser 的解析结果
+    Parse { filename: String },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum IndexerCommand {
+    /// 添加索引器
+    Add {
+        name: String,
+        url: String,
+        #[arg(long, value_enum, default_value_t = Category::Rss)]
+        category: Category,
+        /// 添加后不启用
+        #[arg(long)]
+        disable: bool,
+    },
+    /// 列出所有索引器
+    List,
+    /// 立即拉取所有启用的索引器
+    Refresh,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub(crate) enum Category {
+    Rss,
+    Torznab,
+}
+
+impl From<Category> for IndexerCategory {
+    fn from(value: Category) -> Self {
+        match value {
+            Category::Rss => IndexerCategory::Rss,
+            Category::Torznab => IndexerCategory::Torznab,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub(crate) enum TorrentCommand {
+    /// 搜索数据库中的 torrent，与 torznab 接口的搜索逻辑相同
+    Search {
+        #[arg(default_value = "")]
+        query: String,
+        #[arg(long)]
+        imdb_id: Option<String>,
+        #[arg(long)]
+        tvdb_id: Option<i64>,
+        #[arg(long)]
+        season: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum DbCommand {
+    /// 执行数据库迁移
+    Migrate,
+    /// 备份应用数据库到指定文件
+    Backup { path: PathBuf },
+    /// 整理应用数据库，回收空间
+    Vacuum,
+}
+
+impl TaskCommand {
+    /// 执行子命令，需要先加载数据库
+    pub(crate) async fn run(self) -> Result<()> {
+        match self {
+            TaskCommand::ResetPassword { username } => {
+                let password = read_password()?;
+                let username = User::reset_password(username.as_deref(), &password).await?;
+                println!("reset `{username}` password successfully");
+            }
+            TaskCommand::Indexer(command) => command.run().await?,
+            TaskCommand::Torrent(command) => command.run().await?,
+            TaskCommand::Db(command) => command.run().await?,
+            TaskCommand::Parse { filename } => parse(filename).await,
+        }
+        Ok(())
+    }
+}
+
+/// 从标准输入读取一行作为新密码，交互式终端下输出提示
+fn read_password() -> Result<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprint!("new password: ");
+        std::io::stderr().flush()?;
+    }
+    let mut password = String::new();
+    stdin.read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    ensure!(!password.is_empty(), "password must not be empty");
+    Ok(password.to_string())
+}
+
+impl IndexerCommand {
+    async fn run(self) -> Result<()> {
+        match self {
+            IndexerCommand::Add {
+                name,
+                url,
+                category,
+                disable,
+            } => {
+                let indexer = Indexer {
+                    id: 0,
+                    name,
+                    category: category.into(),
+                    url,
+                    enable: !disable,
+                };
+                indexer.add().await?;
+                println!("indexer added");
+            }
+            IndexerCommand::List => {
+                let param = IndexerSearch::default();
+                for it in Indexer::find_by_param(param).await? {
+                    let enable = if it.enable { "enabled" } else { "disabled" };
+                    let category = format!("{:?}", it.category).to_lowercase();
+                    println!("{}\t{}\t{category}\t{enable}\t{}", it.id, it.name, it.url);
+                }
+            }
+            IndexerCommand::Refresh => {
+                searcher::refresh_indexers(&CancellationToken::new()).await?;
+                println!("indexers refreshed");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TorrentCommand {
+    async fn run(self) -> Result<()> {
+        match self {
+            TorrentCommand::Search {
+                query,
+                imdb_id,
+                tvdb_id,
+                season,
+            } => {
+                let query = TorrentQuery {
+                    name: query,
+                    imdb_id,
+                    tvdb_id,
+                    season,
+                };
+                for it in searcher::search_torrents(query).await? {
+                    let season = format!("S{}E{}", it.season, it.episode);
+                    println!(
+                        "{}\t{}\t{}\t{season}\t{}",
+                        it.id, it.title, it.year, it.name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DbCommand {
+    async fn run(self) -> Result<()> {
+        match self {
+            // 加载数据库时已经执行了迁移
+            DbCommand::Migrate => println!("database is up to date"),
+            DbCommand::Backup { path } => {
+                database::backup(&path).await?;
+                println!("database backup to `{}`", path.display());
+            }
+            DbCommand::Vacuum => {
+                database::vacuum().await?;
+                println!("database vacuum finished");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 依次输出 anitors 的解析结果及 parser 补充影片信息后的结果
+async fn parse(filename: String) {
+    println!("{:#?}", Element::parse(&filename));
+    let mut torrent = Torrent {
+        name: filename,
+        ..Default::default()
+    };
+    if let Err(e) = torrent.try_parse_detail().await {
+        log::warn!("parse torrent detail error: {e}");
+    }
+    println!("{torrent:#?}");
+}
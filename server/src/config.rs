@@ -0,0 +1,375 @@
+//! 启动配置
+//!
+//! 优先级从高到低：命令行参数 > 环境变量 > 配置文件 > 默认值。
+//! 配置文件为 toml 格式，路径通过 `--config` 或 `MK_CONFIG` 指定，
+//! 未指定时读取数据目录下的 `config.toml`（文件不存在时忽略），参考 `config.example.toml`。
+
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context, Result};
+use clap::builder::BoolishValueParser;
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+
+use database::DataConfig;
+
+/// 配置相关的命令行参数，未指定时读取对应的环境变量
+#[derive(Args)]
+pub(crate) struct ConfigArgs {
+    /// 配置文件路径
+    #[arg(long, global = true, env = "MK_CONFIG")]
+    config: Option<PathBuf>,
+    /// 数据目录，存放数据库、日志等运行时数据
+    #[arg(long, global = true, env = "MK_APP_DATA")]
+    data_dir: Option<PathBuf>,
+    /// 资源目录，需要包含 res.sqlite
+    #[arg(long, global = true, env = "MK_RESOURCES")]
+    resource_dir: Option<PathBuf>,
+    /// webui 目录，默认为资源目录下的 dist
+    #[arg(long, global = true, env = "MK_WEBUI")]
+    webui_dir: Option<PathBuf>,
+    /// 监听地址，可以指定多个，默认为 IPv4 及 IPv6 双栈监听
+    #[arg(long, global = true, env = "MK_HOST", value_delimiter = ',')]
+    host: Option<Vec<IpAddr>>,
+    /// 监听端口
+    #[arg(long, global = true, env = "MK_PORT")]
+    port: Option<u16>,
+    /// URL 基础路径，用于反向代理子路径，例如 /mikanarr
+    #[arg(long, global = true, env = "MK_BASE_URL")]
+    base_url: Option<String>,
+    /// TLS 证书文件（PEM 格式）
+    #[arg(long, global = true, env = "MK_TLS_CERT")]
+    tls_cert: Option<PathBuf>,
+    /// TLS 私钥文件（PEM 格式）
+    #[arg(long, global = true, env = "MK_TLS_KEY")]
+    tls_key: Option<PathBuf>,
+    /// 日志级别，支持 tracing 的过滤语法，例如 info,sqlx=warn
+    #[arg(long, global = true, env = "RUST_LOG")]
+    log_level: Option<String>,
+    /// 日志文件路径，默认为数据目录下的 server.log，按周期切分时文件名会附加日期
+    #[arg(long, global = true, env = "MK_LOG_FILE")]
+    log_file: Option<PathBuf>,
+    /// 是否以 JSON 格式输出日志
+    #[arg(long, global = true, env = "MK_LOG_JSON", value_parser = BoolishValueParser::new())]
+    log_json: Option<bool>,
+    /// 日志文件切分周期
+    #[arg(long, global = true, env = "MK_LOG_ROTATION", value_enum)]
+    log_rotation: Option<LogRotation>,
+    /// 保留的日志文件数量，不切分时无效
+    #[arg(long, global = true, env = "MK_LOG_MAX_FILES")]
+    log_max_files: Option<usize>,
+    /// 是否启用 sqlx 日志
+    #[arg(long, global = true, env = "USE_SQLX_LOGGING", value_parser = BoolishValueParser::new())]
+    sqlx_logging: Option<bool>,
+    /// 是否提供 webui 页面，关闭时仅提供 api
+    #[arg(long, global = true, env = "MK_SERVE_WEBUI", value_parser = BoolishValueParser::new())]
+    serve_webui: Option<bool>,
+}
+
+/// 配置文件
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    data_dir: Option<PathBuf>,
+    resource_dir: Option<PathBuf>,
+    webui_dir: Option<PathBuf>,
+    server: FileServerConfig,
+    log: FileLogConfig,
+    sqlx_logging: Option<bool>,
+    serve_webui: Option<bool>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileServerConfig {
+    host: Option<Vec<IpAddr>>,
+    port: Option<u16>,
+    base_url: Option<String>,
+    tls_cert: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileLogConfig {
+    level: Option<String>,
+    file: Option<PathBuf>,
+    json: Option<bool>,
+    rotation: Option<LogRotation>,
+    max_files: Option<usize>,
+}
+
+/// 合并后的启动配置
+pub(crate) struct AppConfig {
+    pub(crate) data_dir: PathBuf,
+    pub(crate) resource_dir: PathBuf,
+    pub(crate) webui_dir: PathBuf,
+    pub(crate) serve_webui: bool,
+    pub(crate) sqlx_logging: bool,
+    pub(crate) server: ServerConfig,
+    pub(crate) log: LogConfig,
+}
+
+/// 服务监听配置
+pub(crate) struct ServerConfig {
+    /// 监听地址，为空时使用 IPv4 及 IPv6 双栈监听
+    pub(crate) hosts: Vec<IpAddr>,
+    pub(crate) port: u16,
+    /// URL 基础路径，为空时挂载在根路径，否则以 `/` 开头且不以 `/` 结尾
+    pub(crate) base_url: String,
+    /// TLS 证书及私钥文件路径，均设置时启用 HTTPS
+    pub(crate) tls: Option<(PathBuf, PathBuf)>,
+}
+
+/// 日志配置
+pub(crate) struct LogConfig {
+    pub(crate) level: String,
+    pub(crate) file: PathBuf,
+    pub(crate) json: bool,
+    pub(crate) rotation: LogRotation,
+    pub(crate) max_files: usize,
+}
+
+/// 日志文件切分周期
+#[derive(Copy, Clone, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogRotation {
+    Hourly,
+    Daily,
+    /// 不切分，始终写入同一个文件
+    Never,
+}
+
+impl AppConfig {
+    /// 读取命令行参数、环境变量及配置文件，并校验配置
+    pub(crate) fn load(cli: ConfigArgs) -> Result<Self> {
+        let file = FileConfig::load(&cli)?;
+        let config = Self::merge(cli, file)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn merge(cli: ConfigArgs, file: FileConfig) -> Result<Self> {
+        let data_dir = cli.data_dir.or(file.data_dir);
+        let data_dir = data_dir.unwrap_or_else(|| PathBuf::from("./data"));
+        let resource_dir = cli.resource_dir.or(file.resource_dir);
+        let resource_dir = resource_dir.unwrap_or_else(|| PathBuf::from("./resources"));
+        let webui_dir = cli.webui_dir.or(file.webui_dir);
+        let webui_dir = webui_dir.unwrap_or_else(|| resource_dir.join("dist"));
+
+        let base_url = cli.base_url.or(file.server.base_url).unwrap_or_default();
+        let base_url = base_url.trim().trim_matches('/');
+        let base_url = match base_url.is_empty() {
+            true => String::default(),
+            false => format!("/{base_url}"),
+        };
+        let tls_cert = cli.tls_cert.or(file.server.tls_cert);
+        let tls_key = cli.tls_key.or(file.server.tls_key);
+        let tls = match (tls_cert, tls_key) {
+            (Some(cert), Some(key)) => Some((cert, key)),
+            (None, None) => None,
+            _ => anyhow::bail!("tls cert and tls key must be set together"),
+        };
+        let server = ServerConfig {
+            hosts: cli.host.or(file.server.host).unwrap_or_default(),
+            port: cli.port.or(file.server.port).unwrap_or(7810),
+            base_url,
+            tls,
+        };
+
+        let log = LogConfig {
+            level: cli.log_level.or(file.log.level).unwrap_or("info".into()),
+            file: cli
+                .log_file
+                .or(file.log.file)
+                .unwrap_or_else(|| data_dir.join("server.log")),
+            json: cli.log_json.or(file.log.json).unwrap_or(false),
+            rotation: cli
+                .log_rotation
+                .or(file.log.rotation)
+                .unwrap_or(LogRotation::Daily),
+            max_files: cli.log_max_files.or(file.log.max_files).unwrap_or(7),
+        };
+
+        Ok(Self {
+            data_dir,
+            resource_dir,
+            webui_dir,
+            serve_webui: cli.serve_webui.or(file.serve_webui).unwrap_or(true),
+            sqlx_logging: cli.sqlx_logging.or(file.sqlx_logging).unwrap_or(false),
+            server,
+            log,
+        })
+    }
+
+    /// 启动前校验，避免运行中才因为路径等问题出错
+    fn validate(&self) -> Result<()> {
+        let res_data = self.res_data_path();
+        ensure!(
+            res_data.is_file(),
+            "res database `{}` not found, check resource dir",
+            res_data.display()
+        );
+        std::fs::create_dir_all(&self.data_dir)
+            .with_context(|| format!("create data dir `{}`", self.data_dir.display()))?;
+        if let Some(dir) = self
+            .log
+            .file
+            .parent()
+            .filter(|it| !it.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("create log dir `{}`", dir.display()))?;
+        }
+        ensure!(
+            self.log.max_files > 0,
+            "log max files must be greater than 0"
+        );
+        if let Some((cert, key)) = &self.server.tls {
+            ensure!(cert.is_file(), "tls cert `{}` not found", cert.display());
+            ensure!(key.is_file(), "tls key `{}` not found", key.display());
+        }
+        Ok(())
+    }
+
+    fn res_data_path(&self) -> PathBuf {
+        self.resource_dir.join("res.sqlite")
+    }
+
+    pub(crate) fn data_config(&self) -> DataConfig {
+        DataConfig {
+            app_data_dir: self.data_dir.clone(),
+            res_data_path: self.res_data_path(),
+            sqlx_logging: self.sqlx_logging,
+        }
+    }
+}
+
+impl FileConfig {
+    /// 读取配置文件，未指定路径且默认文件不存在时使用空配置
+    fn load(cli: &ConfigArgs) -> Result<Self> {
+        let path = match &cli.config {
+            Some(path) => path.clone(),
+            None => {
+                let data_dir = cli.data_dir.as_deref().unwrap_or(Path::new("./data"));
+                let path = data_dir.join("config.toml");
+                if !path.is_file() {
+                    return Ok(Self::default());
+                }
+                path
+            }
+        };
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("read config file `{}`", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("parse config file `{}`", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use clap::Parser;
+
+    use super::*;
+
+    /// 环境变量为进程级状态，解析参数的测试需要串行执行
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        config: ConfigArgs,
+    }
+
+    fn parse_args(args: &[&str]) -> ConfigArgs {
+        let args = std::iter::once("mikanarr").chain(args.iter().copied());
+        TestCli::try_parse_from(args).unwrap().config
+    }
+
+    #[test]
+    fn test_file_config() {
+        let file: FileConfig = toml::from_str(
+            r#"
+            data_dir = "/data"
+            sqlx_logging = true
+
+            [server]
+            port = 8000
+            base_url = "/mikanarr/"
+
+            [log]
+            level = "debug"
+            rotation = "never"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(file.data_dir, Some(PathBuf::from("/data")));
+        assert_eq!(file.sqlx_logging, Some(true));
+        assert_eq!(file.server.port, Some(8000));
+        assert_eq!(file.log.level.as_deref(), Some("debug"));
+        assert!(matches!(file.log.rotation, Some(LogRotation::Never)));
+
+        assert!(toml::from_str::<FileConfig>("unknown = 1").is_err());
+        assert!(toml::from_str::<FileConfig>("[server]\nhost = \"localhost\"").is_err());
+    }
+
+    #[test]
+    fn test_merge_default() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let config = AppConfig::merge(parse_args(&[]), FileConfig::default()).unwrap();
+        assert_eq!(config.data_dir, PathBuf::from("./data"));
+        assert_eq!(config.webui_dir, PathBuf::from("./resources/dist"));
+        assert_eq!(config.log.file, PathBuf::from("./data/server.log"));
+        assert_eq!(config.server.port, 7810);
+        assert_eq!(config.server.base_url, "");
+        assert!(config.server.tls.is_none());
+        assert!(config.serve_webui);
+        assert!(!config.sqlx_logging);
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let file: FileConfig = toml::from_str(
+            r#"
+            data_dir = "/file"
+            serve_webui = false
+
+            [server]
+            port = 8000
+            base_url = "mikanarr/"
+            "#,
+        )
+        .unwrap();
+        let cli = parse_args(&["--port", "9000", "--sqlx-logging", "TRUE"]);
+        let config = AppConfig::merge(cli, file).unwrap();
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.server.base_url, "/mikanarr");
+        assert_eq!(config.data_dir, PathBuf::from("/file"));
+        assert_eq!(config.log.file, PathBuf::from("/file/server.log"));
+        assert!(!config.serve_webui);
+        assert!(config.sqlx_logging);
+
+        let cli = parse_args(&["--tls-cert", "cert.pem"]);
+        assert!(AppConfig::merge(cli, FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_env_precedence() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        std::env::set_var("MK_PORT", "8100");
+        std::env::set_var("USE_SQLX_LOGGING", "TRUE");
+        let file: FileConfig = toml::from_str("[server]\nport = 8000").unwrap();
+        let config = AppConfig::merge(parse_args(&[]), file).unwrap();
+        assert_eq!(config.server.port, 8100);
+        assert!(config.sqlx_logging);
+
+        let file: FileConfig = toml::from_str("[server]\nport = 8000").unwrap();
+        let config = AppConfig::merge(parse_args(&["--port", "9000"]), file).unwrap();
+        assert_eq!(config.server.port, 9000);
+        std::env::remove_var("MK_PORT");
+        std::env::remove_var("USE_SQLX_LOGGING");
+    }
+}
@@ -0,0 +1,87 @@
+//! Prometheus 指标导出
+//!
+//! 各模块通过 `metrics` 宏记录指标，此处安装全局 recorder 并声明指标说明。
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::Result;
+use metrics::{describe_counter, describe_histogram, Unit};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+/// 耗时类指标的分桶（秒）
+const DURATION_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 60.0,
+];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// 安装全局 recorder，需要在记录指标前调用
+pub(crate) fn install() -> Result<()> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets(&DURATION_BUCKETS)?
+        .install_recorder()?;
+    _ = HANDLE.set(handle);
+    describe();
+    Ok(())
+}
+
+/// Prometheus 文本格式的指标，未安装 recorder 时为空
+pub(crate) fn render() -> String {
+    HANDLE.get().map(|it| it.render()).unwrap_or_default()
+}
+
+/// 定期清理过期的直方图数据，避免内存持续增长
+pub(crate) async fn upkeep(token: CancellationToken) {
+    while !token.is_cancelled() {
+        if let Some(handle) = HANDLE.get() {
+            handle.run_upkeep();
+        }
+        tokio::select! {
+            _ = sleep(Duration::from_secs(5)) => {}
+            _ = token.cancelled() => {}
+        }
+    }
+}
+
+fn describe() {
+    describe_counter!(
+        "mikanarr_torrents_fetched_total",
+        "Torrents fetched from indexer"
+    );
+    describe_counter!(
+        "mikanarr_torrents_inserted_total",
+        "New torrents saved to database"
+    );
+    describe_counter!(
+        "mikanarr_indexer_fetch_errors_total",
+        "Indexer fetch failures"
+    );
+    describe_counter!(
+        "mikanarr_parse_failures_total",
+        "Torrent parse failures by stage"
+    );
+    describe_counter!("mikanarr_tmdb_requests_total", "TMDB api requests");
+    describe_histogram!(
+        "mikanarr_tmdb_request_duration_seconds",
+        Unit::Seconds,
+        "TMDB api request latency"
+    );
+    describe_counter!("mikanarr_torznab_queries_total", "Torznab queries by type");
+    describe_histogram!(
+        "mikanarr_torznab_query_duration_seconds",
+        Unit::Seconds,
+        "Torznab search latency"
+    );
+    describe_counter!(
+        "mikanarr_downloader_rpc_errors_total",
+        "Downloader rpc failures by backend"
+    );
+    describe_histogram!(
+        "mikanarr_fetch_loop_duration_seconds",
+        Unit::Seconds,
+        "Duration of one pass over all indexers"
+    );
+}
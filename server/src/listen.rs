@@ -0,0 +1,68 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::{Context, Result};
+use poem::listener::{
+    AcceptorExt, BoxAcceptor, IntoTlsConfigStream, RustlsCertificate, RustlsConfig, TcpAcceptor,
+};
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::config::ServerConfig;
+
+impl ServerConfig {
+    pub(crate) fn acceptor(&self) -> Result<BoxAcceptor> {
+        let mut acceptor = match self.hosts.is_empty() {
+            true => self.bind_default()?,
+            false => {
+                let mut acceptors = Vec::new();
+                for host in &self.hosts {
+                    acceptors.push(bind(SocketAddr::new(*host, self.port))?);
+                }
+                let mut acceptors = acceptors.into_iter();
+                let first = acceptors.next().unwrap().boxed();
+                acceptors.fold(first, |it, other| it.combine(other).boxed())
+            }
+        };
+
+        if let Some((cert, key)) = &self.tls {
+            let cert = std::fs::read(cert)
+                .with_context(|| format!("read tls cert `{}`", cert.display()))?;
+            let key =
+                std::fs::read(key).with_context(|| format!("read tls key `{}`", key.display()))?;
+            let certificate = RustlsCertificate::new().cert(cert).key(key);
+            let config = RustlsConfig::new().fallback(certificate);
+            // 提前校验证书及私钥，避免启动后才在握手时报错
+            acceptor = acceptor.rustls(config.into_stream()?).boxed();
+        }
+
+        Ok(acceptor)
+    }
+
+    /// 优先使用双栈监听，系统不支持 IPv6 时仅监听 IPv4
+    fn bind_default(&self) -> Result<BoxAcceptor> {
+        let addr = SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), self.port);
+        match bind(addr) {
+            Ok(it) => Ok(it.boxed()),
+            Err(e) => {
+                log::warn!("dual-stack listening unavailable, fallback to IPv4: {e}");
+                let addr = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), self.port);
+                Ok(bind(addr)?.boxed())
+            }
+        }
+    }
+}
+
+/// 监听地址，IPv6 未指定地址（`::`）同时接受 IPv4 连接
+fn bind(addr: SocketAddr) -> Result<TcpAcceptor> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(!addr.ip().is_unspecified())?;
+    }
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    socket
+        .bind(&addr.into())
+        .with_context(|| format!("bind `{addr}` failed"))?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+    Ok(TcpAcceptor::from_std(socket.into())?)
+}
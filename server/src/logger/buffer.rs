@@ -0,0 +1,263 @@
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize, Serializer};
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// 内存中保留的日志条数
+const CAPACITY: usize = 2000;
+/// 实时日志的缓冲条数，接收过慢时丢弃旧日志
+const CHANNEL_CAPACITY: usize = 256;
+
+/// 日志记录
+#[derive(Clone, Serialize)]
+pub(crate) struct LogEntry {
+    /// 递增序号，可用于前端去重及增量查询
+    pub(crate) id: u64,
+    /// 记录时间（unix 时间戳，毫秒）
+    time: i64,
+    #[serde(serialize_with = "serialize_level")]
+    level: Level,
+    /// 模块路径
+    target: String,
+    message: String,
+}
+
+fn serialize_level<S: Serializer>(level: &Level, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(level.as_str())
+}
+
+/// 日志查询条件
+#[derive(Deserialize)]
+pub(crate) struct LogSearch {
+    /// 最低日志级别，例如 warn 时返回 warn 及 error
+    level: Option<String>,
+    /// 模块路径前缀
+    module: Option<String>,
+    /// 起始时间（unix 时间戳，毫秒）
+    since: Option<i64>,
+    /// 截止时间（unix 时间戳，毫秒）
+    until: Option<i64>,
+    /// 仅返回序号大于该值的日志
+    after_id: Option<u64>,
+    /// 返回最新的条数，默认 200
+    limit: Option<usize>,
+}
+
+impl LogSearch {
+    fn is_match(&self, level: Option<Level>, entry: &LogEntry) -> bool {
+        level.map_or(true, |it| entry.level <= it)
+            && self
+                .module
+                .as_deref()
+                .map_or(true, |it| entry.target.starts_with(it))
+            && self.since.map_or(true, |it| entry.time >= it)
+            && self.until.map_or(true, |it| entry.time <= it)
+            && self.after_id.map_or(true, |it| entry.id > it)
+    }
+
+    fn level(&self) -> anyhow::Result<Option<Level>> {
+        let level = self.level.as_deref().filter(|it| !it.is_empty());
+        Ok(level.map(str::parse).transpose()?)
+    }
+
+    /// 返回用于过滤实时日志的闭包
+    pub(crate) fn into_filter(self) -> anyhow::Result<impl Fn(&LogEntry) -> bool> {
+        let level = self.level()?;
+        Ok(move |entry: &LogEntry| self.is_match(level, entry))
+    }
+}
+
+/// 环形缓冲，超出容量时丢弃最旧的日志
+struct Entries(VecDeque<LogEntry>);
+
+impl Entries {
+    /// 分配序号后写入，返回写入的日志
+    fn push(&mut self, mut entry: LogEntry) -> LogEntry {
+        entry.id = self.0.back().map_or(1, |it| it.id + 1);
+        if self.0.len() >= CAPACITY {
+            self.0.pop_front();
+        }
+        self.0.push_back(entry.clone());
+        entry
+    }
+
+    fn query(&self, param: &LogSearch) -> anyhow::Result<Vec<LogEntry>> {
+        let level = param.level()?;
+        let limit = param.limit.unwrap_or(200);
+        let entries = self.0.iter().rev().filter(|it| param.is_match(level, it));
+        let mut entries: Vec<_> = entries.take(limit).cloned().collect();
+        entries.reverse();
+        Ok(entries)
+    }
+}
+
+/// 最近日志的环形缓冲，供 web 页面查看
+pub(crate) struct LogBuffer;
+
+impl LogBuffer {
+    fn inner(&self) -> MutexGuard<'static, Entries> {
+        static ENTRIES: OnceLock<Mutex<Entries>> = OnceLock::new();
+        let entries =
+            ENTRIES.get_or_init(|| Mutex::new(Entries(VecDeque::with_capacity(CAPACITY))));
+        // 日志记录过程中 panic 时仍然可以继续使用
+        entries.lock().unwrap_or_else(|it| it.into_inner())
+    }
+
+    fn sender(&self) -> &'static broadcast::Sender<LogEntry> {
+        static SENDER: OnceLock<broadcast::Sender<LogEntry>> = OnceLock::new();
+        SENDER.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+    }
+
+    fn push(&self, entry: LogEntry) {
+        let mut inner = self.inner();
+        let entry = inner.push(entry);
+        // 没有订阅者时发送失败，忽略即可
+        _ = self.sender().send(entry);
+    }
+
+    /// 按条件查询，按时间顺序返回最新的日志
+    pub(crate) fn query(&self, param: &LogSearch) -> anyhow::Result<Vec<LogEntry>> {
+        self.inner().query(param)
+    }
+
+    /// 订阅新产生的日志
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.sender().subscribe()
+    }
+}
+
+/// 将日志写入 [LogBuffer] 的 tracing layer
+pub(super) struct BufferLayer;
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // log crate 的日志需要还原原始的 target 等信息
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        LogBuffer.push(LogEntry {
+            id: 0,
+            time: now_millis(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.0,
+        });
+    }
+}
+
+/// 拼接 message 及其他字段，字段以 `key=value` 形式附加在 message 后
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        let name = field.name();
+        // log crate 转换的日志会附带 log.target 等字段，已经还原到元数据中
+        if name.starts_with("log.") {
+            return;
+        }
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        _ = match name {
+            "message" => write!(self.0, "{value:?}"),
+            _ => write!(self.0, "{name}={value:?}"),
+        };
+    }
+}
+
+fn now_millis() -> i64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH);
+    now.map_or(0, |it| it.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(time: i64, level: Level, target: &str) -> LogEntry {
+        LogEntry {
+            id: 0,
+            time,
+            level,
+            target: target.into(),
+            message: String::new(),
+        }
+    }
+
+    fn search(json: &str) -> LogSearch {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_is_match() {
+        let entry = LogEntry {
+            id: 5,
+            ..entry(1000, Level::WARN, "searcher::fetch")
+        };
+        let is_match = |json| search(json).into_filter().unwrap()(&entry);
+        assert!(is_match("{}"));
+        assert!(is_match(r#"{"level":""}"#));
+        assert!(is_match(r#"{"level":"warn"}"#));
+        assert!(is_match(r#"{"level":"INFO"}"#));
+        assert!(!is_match(r#"{"level":"error"}"#));
+        assert!(is_match(r#"{"module":"searcher"}"#));
+        assert!(!is_match(r#"{"module":"downloader"}"#));
+        assert!(is_match(r#"{"since":1000,"until":1000}"#));
+        assert!(!is_match(r#"{"since":1001}"#));
+        assert!(!is_match(r#"{"until":999}"#));
+        assert!(is_match(r#"{"after_id":4}"#));
+        assert!(!is_match(r#"{"after_id":5}"#));
+        assert!(search(r#"{"level":"verbose"}"#).into_filter().is_err());
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut entries = Entries(VecDeque::new());
+        for i in 0..CAPACITY + 10 {
+            let pushed = entries.push(entry(i as i64, Level::INFO, "server"));
+            assert_eq!(pushed.id, i as u64 + 1);
+        }
+        assert_eq!(entries.0.len(), CAPACITY);
+        assert_eq!(entries.0.front().unwrap().id, 11);
+        assert_eq!(entries.0.back().unwrap().id, CAPACITY as u64 + 10);
+    }
+
+    #[test]
+    fn test_query() {
+        let mut entries = Entries(VecDeque::new());
+        for i in 0..300 {
+            let level = match i % 3 {
+                0 => Level::ERROR,
+                _ => Level::DEBUG,
+            };
+            entries.push(entry(i, level, "server"));
+        }
+
+        // 默认返回最新的 200 条，按时间顺序排列
+        let result = entries.query(&search("{}")).unwrap();
+        let ids: Vec<_> = result.iter().map(|it| it.id).collect();
+        assert_eq!(ids, (101..=300).collect::<Vec<_>>());
+
+        let result = entries
+            .query(&search(r#"{"level":"error","limit":3}"#))
+            .unwrap();
+        let ids: Vec<_> = result.iter().map(|it| it.id).collect();
+        assert_eq!(ids, [292, 295, 298]);
+
+        let result = entries.query(&search(r#"{"after_id":298}"#)).unwrap();
+        let ids: Vec<_> = result.iter().map(|it| it.id).collect();
+        assert_eq!(ids, [299, 300]);
+
+        assert!(entries.query(&search(r#"{"level":"verbose"}"#)).is_err());
+    }
+}
@@ -1,32 +1,84 @@
-use std::fs::File;
 use std::path::Path;
 
+use anyhow::{Context, Result};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+
+use crate::config::{LogConfig, LogRotation};
+
+pub(crate) use buffer::{LogBuffer, LogEntry, LogSearch};
 
 mod banner;
+mod buffer;
 
 fn timer() -> fmt::time::ChronoLocal {
     fmt::time::ChronoLocal::new("%F %T%.3f".into())
 }
 
-pub(crate) fn load() {
-    let log_path = match std::env::var("MK_APP_DATA") {
-        Ok(it) => Path::new(&it).join("server.log"),
-        Err(_) => Path::new("./data").join("server.log"),
-    };
-    let log_file = File::create(log_path).unwrap();
-    let file_output = fmt::layer()
+/// 按配置选择文本或 JSON 格式
+fn format_layer<W>(
+    config: &LogConfig,
+    writer: W,
+    ansi: bool,
+) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'a> fmt::MakeWriter<'a> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
         .with_timer(timer())
-        .with_ansi(false)
-        .with_writer(log_file);
+        .with_ansi(ansi)
+        .with_writer(writer);
+    match config.json {
+        true => layer.json().boxed(),
+        false => layer.boxed(),
+    }
+}
+
+/// 日志文件按周期切分，文件名为 `{文件名}.{日期}.{扩展名}`
+fn file_appender(config: &LogConfig) -> Result<RollingFileAppender> {
+    let file = &config.file;
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let prefix = file.file_stem().and_then(|it| it.to_str());
+    let prefix = prefix.context("invalid log file name")?;
+    let mut builder = RollingFileAppender::builder()
+        .rotation(match config.rotation {
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        })
+        .filename_prefix(prefix)
+        .max_log_files(config.max_files);
+    if let Some(suffix) = file.extension().and_then(|it| it.to_str()) {
+        builder = builder.filename_suffix(suffix);
+    }
+    builder
+        .build(dir)
+        .with_context(|| format!("create log file `{}`", file.display()))
+}
+
+pub(crate) fn load(config: &LogConfig) -> Result<()> {
+    let layers = vec![
+        format_layer(config, std::io::stdout, true),
+        format_layer(config, file_appender(config)?, false),
+        buffer::BufferLayer.boxed(),
+    ];
 
     tracing_subscriber::registry()
-        .with(fmt::layer().with_timer(timer()))
-        .with(file_output)
-        .with(EnvFilter::from_default_env())
+        .with(layers)
+        .with(EnvFilter::try_new(&config.level)?)
         .init();
 
     banner::print_banner();
+    Ok(())
+}
+
+/// 命令行子命令仅输出日志到 stderr，避免覆盖服务的日志文件
+pub(crate) fn load_console(config: &LogConfig) -> Result<()> {
+    tracing_subscriber::registry()
+        .with(format_layer(config, std::io::stderr, true))
+        .with(EnvFilter::try_new(&config.level)?)
+        .init();
+    Ok(())
 }
@@ -1,20 +1,91 @@
+use std::time::Duration;
+
 use anyhow::{Context, Result};
-use poem::listener::TcpListener;
+use clap::Parser;
 use poem::Server;
 
+use database::entity::User;
+
+use cli::{Cli, Command};
+use config::AppConfig;
+use supervisor::Supervisor;
+
+mod cli;
+mod config;
+mod exporter;
+mod listen;
 mod logger;
 mod router;
+mod supervisor;
+
+/// 关闭服务时等待请求及后台任务结束的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
 
-    logger::load();
-    database::load().await;
-    searcher::load();
+    let cli = Cli::parse();
+    let config = AppConfig::load(cli.config)?;
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(config).await,
+        Command::Task(command) => {
+            logger::load_console(&config.log)?;
+            database::load(config.data_config()).await?;
+            command.run().await
+        }
+    }
+}
 
-    Server::new(TcpListener::bind("0.0.0.0:7810"))
-        .run(router::route())
+async fn serve(config: AppConfig) -> Result<()> {
+    logger::load(&config.log)?;
+    database::load(config.data_config()).await?;
+    for (username, password) in User::replace_default_password().await? {
+        log::warn!("initial password of user `{username}`: {password}, change it after login");
+    }
+    exporter::install()?;
+    Supervisor.spawn("metrics_upkeep", exporter::upkeep);
+    Supervisor.spawn("indexer_fetch", searcher::fetch_and_save_torrents);
+    Supervisor.spawn("download_watch", downloader::watch_all);
+    Supervisor.spawn("notifier", notifier::run);
+
+    let result = Server::new_with_acceptor(config.server.acceptor()?)
+        .run_with_graceful_shutdown(
+            router::route(&config),
+            shutdown_signal(),
+            Some(SHUTDOWN_TIMEOUT),
+        )
         .await
-        .context("web service startup failed")
+        .context("web service startup failed");
+    log::info!("web service stopped, waiting for background tasks");
+    Supervisor.shutdown(SHUTDOWN_TIMEOUT).await;
+    result
+}
+
+/// 等待 Ctrl+C 或 SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("listen ctrl-c signal error: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut it) => _ = it.recv().await,
+            Err(e) => {
+                log::error!("listen terminate signal error: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => log::info!("received ctrl-c, shutting down"),
+        _ = terminate => log::info!("received terminate signal, shutting down"),
+    }
 }
@@ -0,0 +1,75 @@
+use poem::web::{Data, Json};
+use poem::{delete, get, handler, post, put, Request, Route};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use database::entity::{ApiKey, User};
+
+use super::auth::API_KEY_PREFIX;
+use super::{audit, ResultResp};
+
+pub(super) fn route() -> Route {
+    Route::new()
+        .nest("/password", put(password))
+        .nest("/apikey/list", get(api_key_list))
+        .nest("/apikey/add", post(api_key_add))
+        .nest("/apikey/delete", delete(api_key_delete))
+}
+
+#[derive(Deserialize)]
+struct PasswordForm {
+    password: String,
+}
+
+/// 修改当前用户的密码
+#[handler]
+async fn password(
+    req: &Request,
+    Data(user): Data<&User>,
+    Json(form): Json<PasswordForm>,
+) -> Json<ResultResp<()>> {
+    let result = User::set_password(user.id, &form.password).await;
+    audit::record(req, "account.password", "", &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[handler]
+async fn api_key_list(Data(user): Data<&User>) -> Json<ResultResp<Vec<ApiKey>>> {
+    let list = ApiKey::find_by_user(user.id).await;
+    Json(ResultResp::from(list))
+}
+
+#[derive(Deserialize)]
+struct ApiKeyForm {
+    name: String,
+}
+
+/// 创建 API key，key 仅在创建时返回一次
+#[handler]
+async fn api_key_add(
+    req: &Request,
+    Data(user): Data<&User>,
+    Json(form): Json<ApiKeyForm>,
+) -> Json<ResultResp<String>> {
+    let key = format!("{API_KEY_PREFIX}{}", Uuid::new_v4().simple());
+    let name = form.name.clone();
+    let result = ApiKey::add(user.id, form.name, &key).await;
+    audit::record(req, "account.apikey_add", name, &result).await;
+    Json(ResultResp::from(result.map(|_| key)))
+}
+
+#[derive(Deserialize)]
+struct DeleteId {
+    id: u32,
+}
+
+#[handler]
+async fn api_key_delete(
+    req: &Request,
+    Data(user): Data<&User>,
+    Json(param): Json<DeleteId>,
+) -> Json<ResultResp<()>> {
+    let result = ApiKey::delete_by_id(param.id, user.id).await;
+    audit::record(req, "account.apikey_delete", param.id, &result).await;
+    Json(ResultResp::from(result))
+}
@@ -0,0 +1,47 @@
+use std::fmt::Display;
+
+use anyhow::Result;
+use poem::web::{Json, Query};
+use poem::{get, handler, Request, Route};
+
+use database::entity::{AuditLog, AuditLogSearch, User};
+
+use super::intranet::client_addr;
+use super::ResultResp;
+
+pub(super) fn route() -> Route {
+    Route::new().nest("/list", get(list))
+}
+
+#[handler]
+async fn list(Query(param): Query<AuditLogSearch>) -> Json<ResultResp<Vec<AuditLog>>> {
+    let list = AuditLog::find_by_param(param).await;
+    Json(ResultResp::from(list))
+}
+
+/// 记录当前用户的操作，记录失败时仅输出日志
+pub(super) async fn record<T>(
+    req: &Request,
+    action: &str,
+    target: impl Display,
+    result: &Result<T>,
+) {
+    let user = req.extensions().get::<User>();
+    let username = user.map_or("", |it| it.username.as_str());
+    record_as(req, username, action, target, result.is_ok()).await;
+}
+
+/// 以指定用户名记录操作，用于登录等未鉴权的请求
+pub(super) async fn record_as(
+    req: &Request,
+    username: &str,
+    action: &str,
+    target: impl Display,
+    success: bool,
+) {
+    let ip = client_addr(req).await;
+    let target = target.to_string();
+    if let Err(e) = AuditLog::add(username, &ip, action, &target, success).await {
+        log::warn!("record audit log error: {e}");
+    }
+}
@@ -1,25 +1,21 @@
-use std::sync::{Mutex, MutexGuard};
-
-use cached::{Cached, TimedSizedCache};
-use once_cell::sync::Lazy as LazyLock;
 use poem::http::header::AUTHORIZATION;
-use poem::web::Json;
+use poem::http::Method;
+use poem::web::{Data, Json};
 use poem::{handler, Endpoint, IntoResponse, Request, Response, Result};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use database::entity::Config;
-use encode::sha256_encode;
+use database::entity::{ApiKey, Config, Session, User, UserRole};
 
+use super::audit;
+use super::intranet::{client_addr, is_intranet};
+use super::limiter::LoginLimiter;
 use super::ResultResp;
 
-fn session() -> MutexGuard<'static, TimedSizedCache<String, ()>> {
-    static SESSION: LazyLock<Mutex<TimedSizedCache<String, ()>>> = LazyLock::new(|| {
-        let cache = TimedSizedCache::with_size_and_lifespan_and_refresh(5, 30 * 60, true);
-        Mutex::new(cache)
-    });
-    SESSION.lock().unwrap()
-}
+/// API key 请求头
+const API_KEY_HEADER: &str = "X-Api-Key";
+/// API key 前缀，用于区分 `Authorization: Bearer` 中的 API key 及会话 token
+pub(super) const API_KEY_PREFIX: &str = "mk_";
 
 #[derive(Deserialize)]
 struct LoginForm {
@@ -29,33 +25,60 @@ struct LoginForm {
 
 /// 登录接口，成功返回 token
 #[handler]
-pub(super) async fn login(Json(res): Json<LoginForm>) -> Json<ResultResp<String>> {
-    // 421 账号错误
-    if res.account != Config.username().await {
-        return Json(ResultResp::from(421));
+pub(super) async fn login(req: &Request, Json(res): Json<LoginForm>) -> Json<ResultResp<String>> {
+    let ip = client_addr(req).await;
+    // 429 登录失败次数过多，暂时锁定
+    if LoginLimiter.is_locked(&ip, &res.account) {
+        audit::record_as(req, &res.account, "login", "locked", false).await;
+        return Json(ResultResp::from(429));
     }
-    // 422 密码错误
-    if sha256_encode(res.password) != Config.password().await {
+
+    let user = match User::verify(&res.account, &res.password).await {
+        Ok(it) => it,
+        Err(e) => return Json(ResultResp::from(Err(e))),
+    };
+    // 422 登录失败，不区分账号不存在及密码错误
+    let Some(user) = user else {
+        LoginLimiter.failed(&ip, &res.account);
+        audit::record_as(req, &res.account, "login", "", false).await;
         return Json(ResultResp::from(422));
-    }
+    };
 
-    log::info!("login user: {}", res.account);
+    log::info!("login user: {}", user.username);
+    LoginLimiter.succeeded(&res.account);
+    audit::record_as(req, &user.username, "login", "", true).await;
     let token = Uuid::new_v4().to_string();
-    session().cache_set(token.clone(), ());
-    Json(ResultResp::from(Ok(token)))
+    let expire_hours = Config.session_expire_hours().await;
+    let result = Session::add(&token, user.id, expire_hours).await;
+    Json(ResultResp::from(result.map(|_| token)))
+}
+
+/// 登出接口，删除当前会话
+#[handler]
+pub(super) async fn logout(req: &Request) -> Json<ResultResp<()>> {
+    let result = match bearer_token(req) {
+        Some(token) => Session::delete_by_token(token).await,
+        None => Ok(()),
+    };
+    audit::record(req, "logout", "", &result).await;
+    Json(ResultResp::from(result))
 }
 
 #[derive(Serialize)]
 struct UserInfo {
     username: String,
+    role: UserRole,
+    must_change_password: bool,
     version: String,
 }
 
 /// 用户信息接口，成功返回 username
 #[handler]
-pub(super) async fn username() -> Json<ResultResp<UserInfo>> {
+pub(super) async fn username(Data(user): Data<&User>) -> Json<ResultResp<UserInfo>> {
     let info = UserInfo {
-        username: Config.username().await,
+        username: user.username.clone(),
+        role: user.role,
+        must_change_password: user.must_change_password,
         version: env!("CARGO_PKG_VERSION").into(),
     };
     Json(ResultResp::from(Ok(info)))
@@ -64,24 +87,85 @@ pub(super) async fn username() -> Json<ResultResp<UserInfo>> {
 /// 鉴权白名单
 static WHITE_LIST: [&str; 2] = ["/login", "/search/torznab"];
 
-pub(super) async fn auth<E: Endpoint>(next: E, req: Request) -> Result<Response> {
+/// 只读用户允许的修改操作（前缀）
+static READ_ONLY_ALLOW: [&str; 2] = ["/logout", "/account/"];
+
+/// 需要修改密码时允许访问的接口
+static PASSWORD_CHANGE_ALLOW: [&str; 3] = ["/logout", "/username", "/account/password"];
+
+/// SSE 接口，浏览器 EventSource 无法设置请求头，允许通过查询参数 `token` 鉴权
+static SSE_PATHS: [&str; 2] = ["/events", "/system/logs/tail"];
+
+/// 仅管理员可以访问的接口（前缀）
+static ADMIN_ONLY: [&str; 4] = ["/user/", "/audit/", "/system/logs", "/notification/"];
+
+pub(super) async fn auth<E: Endpoint>(next: E, mut req: Request) -> Result<Response> {
+    let path = req.uri().path();
     // 需要鉴权的接口
-    if !WHITE_LIST.contains(&req.uri().path()) {
-        let token = req.header(AUTHORIZATION);
-        let token = token.and_then(|it| it.strip_prefix("Bearer "));
-        let token_exist = token
-            .map(|it| session().cache_get(it).is_some())
-            .unwrap_or(false);
-
-        if !token_exist {
-            let invalid_token = Json(ResultResp::<()> {
-                code: 401,
-                message: None,
-                data: None,
-            });
-            return Ok(invalid_token.into_response());
+    if !WHITE_LIST.contains(&path) {
+        // 内网请求跳过鉴权，视为管理员，管理员需要修改密码时同样需要先修改密码
+        let user = match is_intranet(&req).await {
+            true => match User::find_admin().await {
+                Ok(it) => Some(it),
+                Err(e) => return Ok(Json(ResultResp::<()>::from(Err(e))).into_response()),
+            },
+            false => authenticate(&req).await,
+        };
+        let Some(user) = user else {
+            return Ok(Json(ResultResp::<()>::from(401)).into_response());
+        };
+
+        // 428 需要先修改密码
+        if user.must_change_password && !PASSWORD_CHANGE_ALLOW.contains(&path) {
+            return Ok(Json(ResultResp::<()>::from(428)).into_response());
         }
+        let read_only = user.role == UserRole::ReadOnly;
+        let write = req.method() != Method::GET;
+        if read_only && write && !READ_ONLY_ALLOW.iter().any(|it| path.starts_with(it)) {
+            return Ok(Json(ResultResp::<()>::from(403)).into_response());
+        }
+        if user.role != UserRole::Admin && ADMIN_ONLY.iter().any(|it| path.starts_with(it)) {
+            return Ok(Json(ResultResp::<()>::from(403)).into_response());
+        }
+        req.extensions_mut().insert(user);
     }
 
     next.call(req).await.map(|it| it.into_response())
 }
+
+/// 通过 API key 或会话 token 获取当前用户，API key 可以放在 `X-Api-Key` 或 `Authorization: Bearer` 中
+async fn authenticate(req: &Request) -> Option<User> {
+    let user = match req.header(API_KEY_HEADER) {
+        Some(key) => ApiKey::find_user(key).await,
+        None => match request_token(req)?.as_str() {
+            key if key.starts_with(API_KEY_PREFIX) => ApiKey::find_user(key).await,
+            token => Session::find_user(token).await,
+        },
+    };
+    user.unwrap_or_else(|e| {
+        log::warn!("authenticate error: {e}");
+        None
+    })
+}
+
+fn bearer_token(req: &Request) -> Option<&str> {
+    let token = req.header(AUTHORIZATION);
+    token.and_then(|it| it.strip_prefix("Bearer "))
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// 优先使用 `Authorization: Bearer`，SSE 接口可以使用查询参数
+fn request_token(req: &Request) -> Option<String> {
+    if let Some(token) = bearer_token(req) {
+        return Some(token.to_string());
+    }
+    let path = req.uri().path().trim_end_matches('/');
+    if req.method() != Method::GET || !SSE_PATHS.contains(&path) {
+        return None;
+    }
+    req.params::<TokenQuery>().ok()?.token
+}
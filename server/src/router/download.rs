@@ -0,0 +1,206 @@
+use anyhow::{Context, Result};
+use futures_util::future::join_all;
+use poem::web::Json;
+use poem::{delete, get, handler, post, put, Request, Route};
+use serde::{Deserialize, Serialize};
+
+use database::entity::{Downloader, Torrent};
+use downloader::{DownloadClient, DownloadItem};
+
+use super::{audit, ResultResp};
+
+pub(super) fn route() -> Route {
+    Route::new()
+        .at("/", get(list))
+        .nest("/add", post(add))
+        .nest("/pause", post(pause))
+        .nest("/resume", post(resume))
+        .nest("/recheck", post(recheck))
+        .nest("/remove", delete(remove))
+        .nest("/sequential", put(sequential))
+}
+
+/// 单个下载器的下载状态
+#[derive(Serialize)]
+struct DownloaderState {
+    id: u32,
+    name: String,
+    /// 下载基础路径（下载器中的路径）
+    download_dir: String,
+    /// 下载基础路径（经过路径映射后的本地路径）
+    local_download_dir: String,
+    /// 下载速度合计（字节/秒）
+    download_speed: u64,
+    /// 上传速度合计（字节/秒）
+    upload_speed: u64,
+    items: Vec<DownloadItem>,
+    /// 下载器连接或请求错误信息
+    error: Option<String>,
+}
+
+impl DownloaderState {
+    async fn new(downloader: Downloader) -> Self {
+        let id = downloader.id;
+        let name = downloader.name.clone();
+        let mut client = DownloadClient::from(downloader);
+        let download_dir = client.download_dir().to_owned();
+        let local_download_dir = match client.path_mapper().await {
+            Ok(mapper) => mapper.to_local(&download_dir),
+            Err(_) => download_dir.clone(),
+        };
+        let (items, error) = match client.download_list().await {
+            Ok(it) => (it, None),
+            Err(e) => {
+                log::debug!("{e:#?}");
+                log::warn!("get `{name}` download list error: {e}");
+                (Vec::default(), Some(e.to_string()))
+            }
+        };
+        Self {
+            id,
+            name,
+            download_dir,
+            local_download_dir,
+            download_speed: items.iter().map(|it| it.download_speed).sum(),
+            upload_speed: items.iter().map(|it| it.upload_speed).sum(),
+            items,
+            error,
+        }
+    }
+}
+
+/// 所有下载器的下载状态汇总
+#[derive(Serialize)]
+struct Downloads {
+    download_speed: u64,
+    upload_speed: u64,
+    downloaders: Vec<DownloaderState>,
+}
+
+impl Downloads {
+    async fn new() -> Result<Self> {
+        // 并发请求各下载器，避免单个下载器响应慢时阻塞整个列表
+        let downloaders = Downloader::find_all().await?;
+        let downloaders = join_all(downloaders.into_iter().map(DownloaderState::new)).await;
+        Ok(Self {
+            download_speed: downloaders.iter().map(|it| it.download_speed).sum(),
+            upload_speed: downloaders.iter().map(|it| it.upload_speed).sum(),
+            downloaders,
+        })
+    }
+}
+
+#[handler]
+async fn list() -> Json<ResultResp<Downloads>> {
+    Json(ResultResp::from(Downloads::new().await))
+}
+
+/// 添加下载的 torrent
+#[derive(Deserialize)]
+struct TorrentParam {
+    /// torrent hash
+    id: String,
+}
+
+/// 按下载路由规则选择下载器并添加下载，返回使用的下载器 id
+#[handler]
+async fn add(req: &Request, Json(param): Json<TorrentParam>) -> Json<ResultResp<u32>> {
+    let result = async {
+        let torrent = Torrent::find_by_id(&param.id).await?;
+        DownloadClient::download_torrent(&torrent).await
+    };
+    let result = result.await;
+    audit::record(req, "download.add", &param.id, &result).await;
+    Json(ResultResp::from(result))
+}
+
+/// 手动操作的下载项
+#[derive(Deserialize)]
+struct ItemParam {
+    downloader_id: u32,
+    id: String,
+    /// 删除时是否同时删除文件
+    #[serde(default)]
+    delete_data: bool,
+}
+
+#[derive(Copy, Clone)]
+enum ItemAction {
+    Pause,
+    Resume,
+    Recheck,
+    Remove,
+}
+
+impl ItemAction {
+    fn name(self) -> &'static str {
+        match self {
+            ItemAction::Pause => "download.pause",
+            ItemAction::Resume => "download.resume",
+            ItemAction::Recheck => "download.recheck",
+            ItemAction::Remove => "download.remove",
+        }
+    }
+
+    async fn apply(self, param: &ItemParam) -> Result<()> {
+        let downloader = Downloader::find_by_id(param.downloader_id).await?;
+        let downloader = downloader.context("downloader not found")?;
+        let mut client = DownloadClient::from(downloader);
+        match self {
+            ItemAction::Pause => client.pause(&param.id).await,
+            ItemAction::Resume => client.resume(&param.id).await,
+            ItemAction::Recheck => client.recheck(&param.id).await,
+            ItemAction::Remove => client.remove(&param.id, param.delete_data).await,
+        }
+    }
+
+    /// 执行操作并记录审计日志
+    async fn handle(self, req: &Request, param: ItemParam) -> Json<ResultResp<()>> {
+        let result = self.apply(&param).await;
+        let target = format!("{}/{}", param.downloader_id, param.id);
+        audit::record(req, self.name(), target, &result).await;
+        Json(ResultResp::from(result))
+    }
+}
+
+#[handler]
+async fn pause(req: &Request, Json(param): Json<ItemParam>) -> Json<ResultResp<()>> {
+    ItemAction::Pause.handle(req, param).await
+}
+
+#[handler]
+async fn resume(req: &Request, Json(param): Json<ItemParam>) -> Json<ResultResp<()>> {
+    ItemAction::Resume.handle(req, param).await
+}
+
+#[handler]
+async fn recheck(req: &Request, Json(param): Json<ItemParam>) -> Json<ResultResp<()>> {
+    ItemAction::Recheck.handle(req, param).await
+}
+
+#[handler]
+async fn remove(req: &Request, Json(param): Json<ItemParam>) -> Json<ResultResp<()>> {
+    ItemAction::Remove.handle(req, param).await
+}
+
+/// 下载项顺序下载开关
+#[derive(Deserialize)]
+struct SequentialParam {
+    downloader_id: u32,
+    id: String,
+    enable: bool,
+}
+
+#[handler]
+async fn sequential(req: &Request, Json(param): Json<SequentialParam>) -> Json<ResultResp<()>> {
+    let result = async {
+        let downloader = Downloader::find_by_id(param.downloader_id).await?;
+        let downloader = downloader.context("downloader not found")?;
+        let mut client = DownloadClient::from(downloader);
+        client.set_sequential(&param.id, param.enable).await
+    };
+    let result = result.await;
+    let target = format!("{}/{}", param.downloader_id, param.id);
+    audit::record(req, "download.sequential", target, &result).await;
+    Json(ResultResp::from(result))
+}
@@ -0,0 +1,64 @@
+use anyhow::Context;
+use poem::web::{Json, Query};
+use poem::{delete, get, handler, post, put, Request, Route};
+use serde::Deserialize;
+
+use database::entity::{Downloader, DownloaderSearch};
+use downloader::DownloadClient;
+
+use super::{audit, ResultResp};
+
+pub(super) fn route() -> Route {
+    Route::new()
+        .nest("/list", get(list))
+        .nest("/add", post(add))
+        .nest("/modify", put(modify))
+        .nest("/delete", delete(delete_one))
+        .nest("/test", post(connect_test))
+}
+
+#[handler]
+async fn list(Query(param): Query<DownloaderSearch>) -> Json<ResultResp<Vec<Downloader>>> {
+    let list = Downloader::find_by_param(param).await;
+    Json(ResultResp::from(list))
+}
+
+#[handler]
+async fn add(req: &Request, Json(downloader): Json<Downloader>) -> Json<ResultResp<()>> {
+    let name = downloader.name.clone();
+    let result = downloader.add().await;
+    audit::record(req, "downloader.add", name, &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[handler]
+async fn modify(req: &Request, Json(downloader): Json<Downloader>) -> Json<ResultResp<()>> {
+    let (id, name) = (downloader.id, downloader.name.clone());
+    let result = downloader.modify().await;
+    DownloadClient::invalidate(id);
+    audit::record(req, "downloader.modify", name, &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[derive(Deserialize)]
+struct DownloaderId {
+    id: u32,
+}
+
+#[handler]
+async fn delete_one(req: &Request, Json(param): Json<DownloaderId>) -> Json<ResultResp<()>> {
+    let result = Downloader::delete_by_id(param.id).await;
+    DownloadClient::invalidate(param.id);
+    audit::record(req, "downloader.delete", param.id, &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[handler]
+async fn connect_test(Json(param): Json<DownloaderId>) -> Json<ResultResp<()>> {
+    let result = async {
+        let downloader = Downloader::find_by_id(param.id).await?;
+        let downloader = downloader.context("downloader not found")?;
+        DownloadClient::from(downloader).connect_test().await
+    };
+    Json(ResultResp::from(result.await))
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use poem::web::sse::{Event as SseEvent, SSE};
+use poem::{get, handler, Route};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+use eventbus::EventBus;
+
+pub(super) fn route() -> Route {
+    Route::new().at("/", get(events))
+}
+
+/// 通过 SSE 推送应用事件，事件类型为 [eventbus::Event] 的名称，数据为 JSON
+#[handler]
+async fn events() -> SSE {
+    // 接收过慢时会跳过部分事件，页面需要在重连后重新拉取数据
+    let stream = BroadcastStream::new(EventBus.subscribe()).filter_map(|it| {
+        let event = it.ok()?;
+        let data = serde_json::to_string(&event).ok()?;
+        Some(SseEvent::message(data).event_type(event.name()))
+    });
+    SSE::new(stream).keep_alive(Duration::from_secs(15))
+}
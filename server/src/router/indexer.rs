@@ -1,10 +1,10 @@
 use poem::web::{Json, Query};
-use poem::{delete, get, handler, post, put, Route};
+use poem::{delete, get, handler, post, put, Request, Route};
 use serde::Deserialize;
 
 use database::entity::{Indexer, IndexerSearch};
 
-use super::ResultResp;
+use super::{audit, ResultResp};
 
 pub(super) fn route() -> Route {
     Route::new()
@@ -22,14 +22,18 @@ async fn list(Query(param): Query<IndexerSearch>) -> Json<ResultResp<Vec<Indexer
 }
 
 #[handler]
-async fn add(Json(indexer): Json<Indexer>) -> Json<ResultResp<()>> {
+async fn add(req: &Request, Json(indexer): Json<Indexer>) -> Json<ResultResp<()>> {
+    let name = indexer.name.clone();
     let result = indexer.add().await;
+    audit::record(req, "indexer.add", name, &result).await;
     Json(ResultResp::from(result))
 }
 
 #[handler]
-async fn modify(Json(indexer): Json<Indexer>) -> Json<ResultResp<()>> {
+async fn modify(req: &Request, Json(indexer): Json<Indexer>) -> Json<ResultResp<()>> {
+    let name = indexer.name.clone();
     let result = indexer.modify().await;
+    audit::record(req, "indexer.modify", name, &result).await;
     Json(ResultResp::from(result))
 }
 
@@ -39,13 +43,15 @@ struct DeleteId {
 }
 
 #[handler]
-async fn delete_one(Json(param): Json<DeleteId>) -> Json<ResultResp<()>> {
+async fn delete_one(req: &Request, Json(param): Json<DeleteId>) -> Json<ResultResp<()>> {
     let result = Indexer::delete_by_id(param.id).await;
+    audit::record(req, "indexer.delete", param.id, &result).await;
     Json(ResultResp::from(result))
 }
 
 #[handler]
-async fn truncate() -> Json<ResultResp<()>> {
+async fn truncate(req: &Request) -> Json<ResultResp<()>> {
     let result = Indexer::delete_all().await;
+    audit::record(req, "indexer.truncate", "", &result).await;
     Json(ResultResp::from(result))
 }
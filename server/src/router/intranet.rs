@@ -0,0 +1,159 @@
+use std::net::IpAddr;
+
+use anyhow::{Context, Result};
+use ipnet::IpNet;
+use once_cell::sync::Lazy as LazyLock;
+use poem::Request;
+
+use database::entity::Config;
+
+/// 反向代理请求头
+const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+
+/// 默认的内网网段：私有地址、回环地址及链路本地地址
+static PRIVATE_NETS: LazyLock<Vec<IpNet>> = LazyLock::new(|| {
+    let nets = [
+        "10.0.0.0/8",
+        "172.16.0.0/12",
+        "192.168.0.0/16",
+        "127.0.0.0/8",
+        "169.254.0.0/16",
+        "::1/128",
+        "fc00::/7",
+        "fe80::/10",
+    ];
+    nets.iter().map(|it| it.parse().unwrap()).collect()
+});
+
+/// 解析 IP 或 CIDR，单个 IP 视为仅包含自身的网段
+pub(super) fn parse_net(value: &str) -> Result<IpNet> {
+    let value = value.trim();
+    let net = value
+        .parse()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from));
+    net.with_context(|| format!("invalid ip or cidr `{value}`"))
+}
+
+/// 请求是否来自内网，未开启内网免鉴权时始终为 false
+pub(super) async fn is_intranet(req: &Request) -> bool {
+    if !Config.auth_intranet().await {
+        return false;
+    }
+    let Some(remote) = remote_ip(req) else {
+        return false;
+    };
+    let forwarded = forwarded_for(req);
+    let proxies = parse_nets(&Config.trusted_proxies().await);
+    // 直连地址不是可信代理却带有 X-Forwarded-For，可能是未配置可信代理的同机反向代理，
+    // 此时直连地址不代表客户端地址，不视为内网请求
+    if !forwarded.is_empty() && !proxies.iter().any(|it| it.contains(&remote)) {
+        return false;
+    }
+    let Some(ip) = resolve_client_ip(remote, &forwarded, &proxies) else {
+        return false;
+    };
+    let cidrs = Config.intranet_cidrs().await;
+    match cidrs.is_empty() {
+        true => PRIVATE_NETS.iter().any(|it| it.contains(&ip)),
+        false => parse_nets(&cidrs).iter().any(|it| it.contains(&ip)),
+    }
+}
+
+/// 客户端地址，用于登录限制及审计日志，无法确定时使用直连地址（不含端口）
+pub(super) async fn client_addr(req: &Request) -> String {
+    match client_ip(req).await.or_else(|| remote_ip(req)) {
+        Some(ip) => ip.to_string(),
+        None => req.remote_addr().to_string(),
+    }
+}
+
+/// 获取客户端 IP，仅当直连地址为可信代理时使用 X-Forwarded-For
+async fn client_ip(req: &Request) -> Option<IpAddr> {
+    let remote = remote_ip(req)?;
+    let proxies = parse_nets(&Config.trusted_proxies().await);
+    resolve_client_ip(remote, &forwarded_for(req), &proxies)
+}
+
+/// 直连地址
+fn remote_ip(req: &Request) -> Option<IpAddr> {
+    Some(req.remote_addr().as_socket_addr()?.ip().to_canonical())
+}
+
+/// X-Forwarded-For 中的所有地址，按代理顺序排列
+fn forwarded_for(req: &Request) -> Vec<&str> {
+    let forwarded = req.headers().get_all(FORWARDED_FOR_HEADER).iter();
+    let forwarded = forwarded.filter_map(|it| it.to_str().ok());
+    forwarded.flat_map(|it| it.split(',')).collect()
+}
+
+fn resolve_client_ip(remote: IpAddr, forwarded: &[&str], proxies: &[IpNet]) -> Option<IpAddr> {
+    let is_trusted = |ip: &IpAddr| proxies.iter().any(|it| it.contains(ip));
+    if !is_trusted(&remote) {
+        return Some(remote);
+    }
+
+    // 从右向左跳过可信代理，第一个非可信代理的地址即为客户端地址
+    // 经过代理但无法确定客户端地址时，不视为内网请求
+    let mut client = None;
+    for ip in forwarded.iter().rev() {
+        let ip = ip.trim().parse::<IpAddr>().ok()?.to_canonical();
+        client = Some(ip);
+        if !is_trusted(&ip) {
+            break;
+        }
+    }
+    client
+}
+
+fn parse_nets(values: &[String]) -> Vec<IpNet> {
+    let nets = values.iter().map(|it| parse_net(it));
+    nets.filter_map(|it| it.map_err(|e| log::warn!("{e}")).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn nets(values: &[&str]) -> Vec<IpNet> {
+        values.iter().map(|it| parse_net(it).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_net() {
+        let net = parse_net("192.168.1.0/24").unwrap();
+        assert!(net.contains(&ip("192.168.1.20")));
+        assert!(!net.contains(&ip("192.168.2.1")));
+        let net = parse_net(" 10.0.0.1 ").unwrap();
+        assert!(net.contains(&ip("10.0.0.1")));
+        assert!(!net.contains(&ip("10.0.0.2")));
+        assert!(parse_net("fd00::/8").unwrap().contains(&ip("fd12::1")));
+        assert!(parse_net("10.0.0.0/33").is_err());
+        assert!(PRIVATE_NETS.iter().any(|it| it.contains(&ip("172.20.1.1"))));
+        assert!(!PRIVATE_NETS.iter().any(|it| it.contains(&ip("8.8.8.8"))));
+    }
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let proxies = nets(&["127.0.0.1", "10.0.0.0/8"]);
+        let resolve =
+            |remote, forwarded: &[&str]| resolve_client_ip(ip(remote), forwarded, &proxies);
+
+        // 非可信代理直连时忽略 X-Forwarded-For
+        assert_eq!(resolve("1.2.3.4", &["192.168.1.1"]), Some(ip("1.2.3.4")));
+        // 可信代理转发时从右向左跳过可信代理
+        assert_eq!(resolve("127.0.0.1", &["1.2.3.4"]), Some(ip("1.2.3.4")));
+        let forwarded = ["6.6.6.6", " 1.2.3.4", " 10.0.0.2"];
+        assert_eq!(resolve("127.0.0.1", &forwarded), Some(ip("1.2.3.4")));
+        // 全部为可信代理时使用最左侧地址
+        assert_eq!(resolve("127.0.0.1", &["10.0.0.3"]), Some(ip("10.0.0.3")));
+        // 地址格式错误或缺失时无法确定客户端
+        assert_eq!(resolve("127.0.0.1", &["unknown", "10.0.0.2"]), None);
+        assert_eq!(resolve("127.0.0.1", &["1.2.3.4", "bad"]), None);
+        assert_eq!(resolve("127.0.0.1", &[]), None);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 统计登录失败次数的时间窗口，同时也是锁定时长
+const WINDOW: Duration = Duration::from_secs(15 * 60);
+/// 单个账号在时间窗口内允许的失败次数
+const ACCOUNT_MAX_FAILURES: u32 = 5;
+/// 单个 IP 在时间窗口内允许的失败次数
+const IP_MAX_FAILURES: u32 = 20;
+
+/// 登录失败记录
+struct Failures {
+    count: u32,
+    first_at: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl Failures {
+    fn is_locked(&self, now: Instant) -> bool {
+        self.locked_until.is_some_and(|it| it > now)
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        !self.is_locked(now) && now.duration_since(self.first_at) > WINDOW
+    }
+}
+
+/// 所有 IP 及账号的登录失败记录
+#[derive(Default)]
+struct Records(HashMap<String, Failures>);
+
+impl Records {
+    fn is_locked(&self, ip: &str, account: &str, now: Instant) -> bool {
+        let keys = [ip_key(ip), account_key(account)];
+        keys.iter()
+            .any(|it| self.0.get(it).is_some_and(|it| it.is_locked(now)))
+    }
+
+    fn failed(&mut self, ip: &str, account: &str, now: Instant) {
+        self.0.retain(|_, it| !it.is_expired(now));
+        let keys = [
+            (ip_key(ip), IP_MAX_FAILURES),
+            (account_key(account), ACCOUNT_MAX_FAILURES),
+        ];
+        for (key, max) in keys {
+            let failures = self.0.entry(key.clone()).or_insert(Failures {
+                count: 0,
+                first_at: now,
+                locked_until: None,
+            });
+            failures.count += 1;
+            if failures.count >= max {
+                log::warn!("too many login failures, lock `{key}`");
+                failures.locked_until = Some(now + WINDOW);
+            }
+        }
+    }
+
+    fn succeeded(&mut self, account: &str) {
+        self.0.remove(&account_key(account));
+    }
+}
+
+/// 登录限制，按 IP 及账号分别统计失败次数，超过限制后锁定一段时间
+pub(super) struct LoginLimiter;
+
+impl LoginLimiter {
+    fn inner(&self) -> MutexGuard<'static, Records> {
+        static RECORDS: OnceLock<Mutex<Records>> = OnceLock::new();
+        RECORDS.get_or_init(Default::default).lock().unwrap()
+    }
+
+    /// IP 或账号是否已被锁定
+    pub(super) fn is_locked(&self, ip: &str, account: &str) -> bool {
+        self.inner().is_locked(ip, account, Instant::now())
+    }
+
+    /// 记录一次登录失败
+    pub(super) fn failed(&self, ip: &str, account: &str) {
+        self.inner().failed(ip, account, Instant::now());
+    }
+
+    /// 登录成功后清除账号的失败记录，IP 的记录保留以限制对多个账号的尝试
+    pub(super) fn succeeded(&self, account: &str) {
+        self.inner().succeeded(account);
+    }
+}
+
+fn ip_key(ip: &str) -> String {
+    format!("ip:{ip}")
+}
+
+fn account_key(account: &str) -> String {
+    format!("account:{account}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_account_lock() {
+        let mut records = Records::default();
+        let now = Instant::now();
+        for _ in 0..ACCOUNT_MAX_FAILURES - 1 {
+            records.failed("1.1.1.1", "admin", now);
+        }
+        assert!(!records.is_locked("1.1.1.1", "admin", now));
+        records.failed("1.1.1.1", "admin", now);
+        // 账号锁定，与 IP 无关
+        assert!(records.is_locked("2.2.2.2", "admin", now));
+        assert!(!records.is_locked("1.1.1.1", "other", now));
+        // 锁定时长结束后解锁，失败记录过期
+        let later = now + WINDOW + Duration::from_secs(1);
+        assert!(!records.is_locked("1.1.1.1", "admin", later));
+        records.failed("1.1.1.1", "admin", later);
+        assert_eq!(records.0[&account_key("admin")].count, 1);
+    }
+
+    #[test]
+    fn test_ip_lock() {
+        let mut records = Records::default();
+        let now = Instant::now();
+        for i in 0..IP_MAX_FAILURES {
+            records.failed("1.1.1.1", &format!("user{i}"), now);
+        }
+        assert!(records.is_locked("1.1.1.1", "new", now));
+        assert!(!records.is_locked("2.2.2.2", "new", now));
+    }
+
+    #[test]
+    fn test_window() {
+        let mut records = Records::default();
+        let now = Instant::now();
+        for _ in 0..ACCOUNT_MAX_FAILURES - 1 {
+            records.failed("1.1.1.1", "admin", now);
+        }
+        // 时间窗口外的失败重新计数
+        let later = now + WINDOW + Duration::from_secs(1);
+        records.failed("1.1.1.1", "admin", later);
+        assert!(!records.is_locked("1.1.1.1", "admin", later));
+        // 登录成功后清除账号记录
+        records.succeeded("admin");
+        assert!(!records.0.contains_key(&account_key("admin")));
+        assert!(records.0.contains_key(&ip_key("1.1.1.1")));
+    }
+}
@@ -0,0 +1,44 @@
+use poem::web::{Json, Query};
+use poem::{delete, get, handler, post, put, Route};
+use serde::Deserialize;
+
+use database::entity::{PathMapping, PathMappingSearch};
+
+use super::ResultResp;
+
+pub(super) fn route() -> Route {
+    Route::new()
+        .nest("/list", get(list))
+        .nest("/add", post(add))
+        .nest("/modify", put(modify))
+        .nest("/delete", delete(delete_one))
+}
+
+#[handler]
+async fn list(Query(param): Query<PathMappingSearch>) -> Json<ResultResp<Vec<PathMapping>>> {
+    let list = PathMapping::find_by_param(param).await;
+    Json(ResultResp::from(list))
+}
+
+#[handler]
+async fn add(Json(mapping): Json<PathMapping>) -> Json<ResultResp<()>> {
+    let result = mapping.add().await;
+    Json(ResultResp::from(result))
+}
+
+#[handler]
+async fn modify(Json(mapping): Json<PathMapping>) -> Json<ResultResp<()>> {
+    let result = mapping.modify().await;
+    Json(ResultResp::from(result))
+}
+
+#[derive(Deserialize)]
+struct DeleteId {
+    id: u32,
+}
+
+#[handler]
+async fn delete_one(Json(param): Json<DeleteId>) -> Json<ResultResp<()>> {
+    let result = PathMapping::delete_by_id(param.id).await;
+    Json(ResultResp::from(result))
+}
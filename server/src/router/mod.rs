@@ -1,30 +1,74 @@
+use std::path::Path;
+
 use anyhow::Result;
 use poem::endpoint::StaticFilesEndpoint;
 use poem::{get, post, EndpointExt, Route};
 use serde::Serialize;
 
+use crate::config::AppConfig;
+
+mod account;
+mod audit;
 mod auth;
+mod download;
+mod downloader;
+mod event;
 mod indexer;
+mod intranet;
+mod limiter;
+mod mapping;
+mod notification;
+mod rule;
 mod setting;
+mod system;
+mod user;
 
-pub(crate) fn route() -> Route {
-    Route::new()
-        .nest("/api", api_route().around(auth::auth))
-        .nest("/", static_files())
+/// base_url 不为空时所有接口及页面挂载在该路径下
+pub(crate) fn route(config: &AppConfig) -> Route {
+    system::init_uptime();
+    let mut route = Route::new()
+        .nest("/health", get(system::health))
+        .nest("/metrics", get(system::prometheus).around(auth::auth))
+        .nest("/api", api_route().around(auth::auth));
+    // webui 为可选资源，目录不存在时仅提供 api
+    if config.serve_webui {
+        match config.webui_dir.is_dir() {
+            true => route = route.nest("/", static_files(&config.webui_dir)),
+            false => log::warn!("webui dir `{}` not found", config.webui_dir.display()),
+        }
+    }
+    let base_url = config.server.base_url.as_str();
+    match base_url.is_empty() {
+        true => route,
+        false => {
+            log::info!("web service base url: `{base_url}`");
+            Route::new().nest(base_url, route)
+        }
+    }
 }
 
 fn api_route() -> Route {
     Route::new()
         .nest("/login", post(auth::login))
+        .nest("/logout", post(auth::logout))
         .nest("/username", get(auth::username))
+        .nest("/account", account::route())
+        .nest("/user", user::route())
+        .nest("/audit", audit::route())
         .nest("/search", searcher::search())
         .nest("/indexer", indexer::route())
+        .nest("/downloader", downloader::route())
+        .nest("/downloads", download::route())
+        .nest("/events", event::route())
+        .nest("/rule", rule::route())
+        .nest("/mapping", mapping::route())
+        .nest("/notification", notification::route())
         .nest("/setting", setting::route())
+        .nest("/system", system::route())
 }
 
-fn static_files() -> StaticFilesEndpoint {
-    let res_data_path = "./resources/dist";
-    log::info!("webui dir path: `{}`", res_data_path);
+fn static_files(res_data_path: &Path) -> StaticFilesEndpoint {
+    log::info!("webui dir path: `{}`", res_data_path.display());
     StaticFilesEndpoint::new(res_data_path)
         .show_files_listing()
         .index_file("index.html")
@@ -0,0 +1,68 @@
+use anyhow::Context;
+use poem::web::{Json, Query};
+use poem::{delete, get, handler, post, put, Request, Route};
+use serde::Deserialize;
+
+use database::entity::{Notification, NotificationSearch};
+
+use super::{audit, ResultResp};
+
+pub(super) fn route() -> Route {
+    Route::new()
+        .nest("/list", get(list))
+        .nest("/add", post(add))
+        .nest("/modify", put(modify))
+        .nest("/delete", delete(delete_one))
+        .nest("/test", post(send_test))
+}
+
+#[handler]
+async fn list(Query(param): Query<NotificationSearch>) -> Json<ResultResp<Vec<Notification>>> {
+    let list = Notification::find_by_param(param).await;
+    Json(ResultResp::from(list))
+}
+
+#[handler]
+async fn add(req: &Request, Json(notification): Json<Notification>) -> Json<ResultResp<()>> {
+    let name = notification.name.clone();
+    let result = match notifier::validate(&notification) {
+        Ok(_) => notification.add().await,
+        Err(e) => Err(e),
+    };
+    audit::record(req, "notification.add", name, &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[handler]
+async fn modify(req: &Request, Json(notification): Json<Notification>) -> Json<ResultResp<()>> {
+    let name = notification.name.clone();
+    let result = match notifier::validate(&notification) {
+        Ok(_) => notification.modify().await,
+        Err(e) => Err(e),
+    };
+    audit::record(req, "notification.modify", name, &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[derive(Deserialize)]
+struct NotificationId {
+    id: u32,
+}
+
+#[handler]
+async fn delete_one(req: &Request, Json(param): Json<NotificationId>) -> Json<ResultResp<()>> {
+    let result = Notification::delete_by_id(param.id).await;
+    audit::record(req, "notification.delete", param.id, &result).await;
+    Json(ResultResp::from(result))
+}
+
+/// 发送测试消息，用于检查渠道配置
+#[handler]
+async fn send_test(Json(param): Json<NotificationId>) -> Json<ResultResp<()>> {
+    let result = async {
+        let notification = Notification::find_by_id(param.id).await?;
+        let notification = notification.context("notification not found")?;
+        notifier::send_test(&notification).await
+    };
+    Json(ResultResp::from(result.await))
+}
@@ -0,0 +1,44 @@
+use poem::web::{Json, Query};
+use poem::{delete, get, handler, post, put, Route};
+use serde::Deserialize;
+
+use database::entity::{DownloadRule, DownloadRuleSearch};
+
+use super::ResultResp;
+
+pub(super) fn route() -> Route {
+    Route::new()
+        .nest("/list", get(list))
+        .nest("/add", post(add))
+        .nest("/modify", put(modify))
+        .nest("/delete", delete(delete_one))
+}
+
+#[handler]
+async fn list(Query(param): Query<DownloadRuleSearch>) -> Json<ResultResp<Vec<DownloadRule>>> {
+    let list = DownloadRule::find_by_param(param).await;
+    Json(ResultResp::from(list))
+}
+
+#[handler]
+async fn add(Json(rule): Json<DownloadRule>) -> Json<ResultResp<()>> {
+    let result = rule.add().await;
+    Json(ResultResp::from(result))
+}
+
+#[handler]
+async fn modify(Json(rule): Json<DownloadRule>) -> Json<ResultResp<()>> {
+    let result = rule.modify().await;
+    Json(ResultResp::from(result))
+}
+
+#[derive(Deserialize)]
+struct DeleteId {
+    id: u32,
+}
+
+#[handler]
+async fn delete_one(Json(param): Json<DeleteId>) -> Json<ResultResp<()>> {
+    let result = DownloadRule::delete_by_id(param.id).await;
+    Json(ResultResp::from(result))
+}
@@ -1,53 +1,107 @@
+use std::ops::Range;
+
 use anyhow::Result;
 use poem::web::Json;
-use poem::{get, handler, put, Route};
+use poem::{get, handler, post, put, Request, Route};
 use serde::{Deserialize, Serialize};
 
 use database::entity::Config;
 
-use super::ResultResp;
+use super::intranet::parse_net;
+use super::{audit, ResultResp};
 
 pub(super) fn route() -> Route {
     Route::new()
         .nest("/info", get(info))
         .nest("/modify", put(modify))
+        .nest("/torznab_apikey", get(torznab_apikey))
+        .nest(
+            "/torznab_apikey/regenerate",
+            post(regenerate_torznab_apikey),
+        )
 }
 
 #[derive(Serialize, Deserialize)]
 struct Settings {
     bangumi_default_status: Option<bool>,
-    username: Option<String>,
-    password: Option<String>,
     auth_intranet: Option<bool>,
+    /// 内网免鉴权的网段，为空时使用私有地址网段
+    intranet_cidrs: Option<Vec<String>>,
+    /// 可信的反向代理地址
+    trusted_proxies: Option<Vec<String>>,
+    /// 登录会话有效期（小时）
+    session_expire_hours: Option<u32>,
+    /// 内置下载器监听端口范围，如 `4240-4260`
+    embedded_listen_ports: Option<String>,
+    /// 内置下载器新添加的 torrent 是否按顺序下载
+    embedded_sequential: Option<bool>,
 }
 
 impl Settings {
     async fn new() -> Self {
         Self {
             bangumi_default_status: Some(Config.bangumi_default_status().await),
-            username: Some(Config.username().await),
-            password: None,
             auth_intranet: Some(Config.auth_intranet().await),
+            intranet_cidrs: Some(Config.intranet_cidrs().await),
+            trusted_proxies: Some(Config.trusted_proxies().await),
+            session_expire_hours: Some(Config.session_expire_hours().await),
+            embedded_listen_ports: Some(ports(Config.embedded_listen_ports().await)),
+            embedded_sequential: Some(Config.embedded_sequential().await),
         }
     }
 
     async fn save(self) -> Result<()> {
+        let nets = self
+            .intranet_cidrs
+            .iter()
+            .chain(self.trusted_proxies.iter());
+        for net in nets.flatten() {
+            parse_net(net)?;
+        }
+        // 端口范围格式错误时不保存任何设置
+        Config
+            .set_embedded_listen_ports(self.embedded_listen_ports)
+            .await?;
         Config
             .set_bangumi_default_status(self.bangumi_default_status)
             .await?;
-        Config.set_username(self.username).await?;
-        Config.set_password(self.password).await?;
         Config.set_auth_intranet(self.auth_intranet).await?;
+        Config.set_intranet_cidrs(self.intranet_cidrs).await?;
+        Config.set_trusted_proxies(self.trusted_proxies).await?;
+        Config
+            .set_session_expire_hours(self.session_expire_hours)
+            .await?;
+        Config
+            .set_embedded_sequential(self.embedded_sequential)
+            .await?;
         Ok(())
     }
 }
 
+fn ports(range: Range<u16>) -> String {
+    format!("{}-{}", range.start, range.end)
+}
+
 #[handler]
 async fn info() -> Json<ResultResp<Settings>> {
     Json(ResultResp::from(Ok(Settings::new().await)))
 }
 
 #[handler]
-async fn modify(Json(setting): Json<Settings>) -> Json<ResultResp<()>> {
-    Json(ResultResp::from(setting.save().await))
+async fn modify(req: &Request, Json(setting): Json<Settings>) -> Json<ResultResp<()>> {
+    let result = setting.save().await;
+    audit::record(req, "setting.modify", "", &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[handler]
+async fn torznab_apikey() -> Json<ResultResp<String>> {
+    Json(ResultResp::from(Config.torznab_apikey().await))
+}
+
+#[handler]
+async fn regenerate_torznab_apikey(req: &Request) -> Json<ResultResp<String>> {
+    let result = Config.regenerate_torznab_apikey().await;
+    audit::record(req, "setting.torznab_apikey", "", &result).await;
+    Json(ResultResp::from(result))
 }
@@ -0,0 +1,202 @@
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use poem::http::StatusCode;
+use poem::web::sse::{Event, SSE};
+use poem::web::{Json, Query};
+use poem::{get, handler, IntoResponse, Response, Route};
+use serde::Serialize;
+use tokio::time::timeout;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+use database::entity::{Downloader, Indexer};
+use database::DataStatus;
+use downloader::DownloadClient;
+use tmdb::Tmdb;
+
+use crate::logger::{LogBuffer, LogEntry, LogSearch};
+use crate::supervisor::{Supervisor, TaskInfo};
+
+use super::ResultResp;
+
+/// 单项连接测试的超时时间
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(super) fn route() -> Route {
+    Route::new()
+        .nest("/tasks", get(tasks))
+        .nest("/status", get(status))
+        .nest("/logs", get(logs))
+        .nest("/logs/tail", get(tail_logs))
+}
+
+/// 记录服务启动时间，用于计算运行时长
+pub(super) fn init_uptime() {
+    started_at();
+}
+
+fn started_at() -> Instant {
+    static STARTED_AT: OnceLock<Instant> = OnceLock::new();
+    *STARTED_AT.get_or_init(Instant::now)
+}
+
+#[handler]
+async fn tasks() -> Json<ResultResp<Vec<TaskInfo>>> {
+    Json(ResultResp::from(Ok(Supervisor.tasks())))
+}
+
+#[handler]
+async fn logs(Query(param): Query<LogSearch>) -> Json<ResultResp<Vec<LogEntry>>> {
+    Json(ResultResp::from(LogBuffer.query(&param)))
+}
+
+/// 通过 SSE 推送新产生的日志，过滤条件与日志查询相同
+#[handler]
+async fn tail_logs(Query(param): Query<LogSearch>) -> Response {
+    let filter = match param.into_filter() {
+        Ok(it) => it,
+        Err(e) => return Json(ResultResp::<()>::from(Err(e))).into_response(),
+    };
+    // 订阅者接收过慢时会跳过部分日志，此处忽略
+    let stream = BroadcastStream::new(LogBuffer.subscribe()).filter_map(move |it| {
+        let entry = it.ok().filter(&filter)?;
+        let data = serde_json::to_string(&entry).ok()?;
+        Some(
+            Event::message(data)
+                .event_type("log")
+                .id(entry.id.to_string()),
+        )
+    });
+    SSE::new(stream)
+        .keep_alive(Duration::from_secs(15))
+        .into_response()
+}
+
+/// 健康检查，仅检查本地状态，数据库不可用或有任务等待重启时返回 503
+#[derive(Serialize)]
+struct Health {
+    healthy: bool,
+    version: &'static str,
+    /// 运行时长（秒）
+    uptime: u64,
+}
+
+/// 无需鉴权，供容器编排及监控探测，详细状态见 /api/system/status
+#[handler]
+pub(super) async fn health() -> Response {
+    let database = database::status().await;
+    let healthy = database.is_ok() && !Supervisor.has_restarting();
+    let health = Health {
+        healthy,
+        version: env!("CARGO_PKG_VERSION"),
+        uptime: started_at().elapsed().as_secs(),
+    };
+    let code = match healthy {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    Json(health).with_status(code).into_response()
+}
+
+/// Prometheus 指标，与 api 相同需要鉴权，可以使用 API key（支持 `Authorization: Bearer`）或内网免鉴权
+#[handler]
+pub(super) async fn prometheus() -> Response {
+    let content_type = "text/plain; version=0.0.4; charset=utf-8";
+    crate::exporter::render()
+        .with_content_type(content_type)
+        .into_response()
+}
+
+/// 连接测试结果
+#[derive(Serialize)]
+struct Check {
+    reachable: bool,
+    error: Option<String>,
+}
+
+impl Check {
+    async fn run(test: impl Future<Output = Result<()>>) -> Self {
+        let result = match timeout(CHECK_TIMEOUT, test).await {
+            Ok(it) => it,
+            Err(_) => Err(anyhow::anyhow!("connect timeout")),
+        };
+        Self {
+            reachable: result.is_ok(),
+            error: result.err().map(|it| it.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct NamedCheck {
+    id: u32,
+    name: String,
+    #[serde(flatten)]
+    check: Check,
+}
+
+#[derive(Serialize)]
+struct SystemStatus {
+    version: &'static str,
+    /// 运行时长（秒）
+    uptime: u64,
+    database: DataStatus,
+    indexers: Vec<NamedCheck>,
+    downloaders: Vec<NamedCheck>,
+    tmdb: Check,
+    tasks: Vec<TaskInfo>,
+    /// 最后一次完成拉取索引器的时间（unix 时间戳，秒）
+    last_fetch_time: Option<u64>,
+}
+
+#[handler]
+async fn status() -> Json<ResultResp<SystemStatus>> {
+    Json(ResultResp::from(system_status().await))
+}
+
+/// 各项连接测试并发执行，单项超时不影响其他结果
+async fn system_status() -> Result<SystemStatus> {
+    let database = database::status().await?;
+
+    let indexers = Indexer::find_all_enable().await?.into_iter().map(|it| {
+        tokio::spawn(async move {
+            let check = Check::run(searcher::connect_test(&it)).await;
+            NamedCheck {
+                id: it.id,
+                name: it.name,
+                check,
+            }
+        })
+    });
+    let indexers: Vec<_> = indexers.collect();
+    let downloaders = Downloader::find_all().await?.into_iter().map(|it| {
+        tokio::spawn(async move {
+            let (id, name) = (it.id, it.name.clone());
+            let check = Check::run(DownloadClient::from(it).connect_test()).await;
+            NamedCheck { id, name, check }
+        })
+    });
+    let downloaders: Vec<_> = downloaders.collect();
+    let tmdb = tokio::spawn(async { Check::run(Tmdb::default().connect_test()).await });
+
+    let mut system = SystemStatus {
+        version: env!("CARGO_PKG_VERSION"),
+        uptime: started_at().elapsed().as_secs(),
+        database,
+        indexers: Vec::new(),
+        downloaders: Vec::new(),
+        tmdb: tmdb.await?,
+        tasks: Supervisor.tasks(),
+        last_fetch_time: searcher::last_fetch_time(),
+    };
+    for it in indexers {
+        system.indexers.push(it.await?);
+    }
+    for it in downloaders {
+        system.downloaders.push(it.await?);
+    }
+    Ok(system)
+}
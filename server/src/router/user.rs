@@ -0,0 +1,49 @@
+use poem::web::Json;
+use poem::{delete, get, handler, post, put, Request, Route};
+use serde::Deserialize;
+
+use database::entity::User;
+
+use super::{audit, ResultResp};
+
+pub(super) fn route() -> Route {
+    Route::new()
+        .nest("/list", get(list))
+        .nest("/add", post(add))
+        .nest("/modify", put(modify))
+        .nest("/delete", delete(delete_one))
+}
+
+#[handler]
+async fn list() -> Json<ResultResp<Vec<User>>> {
+    let list = User::find_all().await;
+    Json(ResultResp::from(list))
+}
+
+#[handler]
+async fn add(req: &Request, Json(user): Json<User>) -> Json<ResultResp<()>> {
+    let username = user.username.clone();
+    let result = user.add().await;
+    audit::record(req, "user.add", username, &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[handler]
+async fn modify(req: &Request, Json(user): Json<User>) -> Json<ResultResp<()>> {
+    let username = user.username.clone();
+    let result = user.modify().await;
+    audit::record(req, "user.modify", username, &result).await;
+    Json(ResultResp::from(result))
+}
+
+#[derive(Deserialize)]
+struct DeleteId {
+    id: u32,
+}
+
+#[handler]
+async fn delete_one(req: &Request, Json(param): Json<DeleteId>) -> Json<ResultResp<()>> {
+    let result = User::delete_by_id(param.id).await;
+    audit::record(req, "user.delete", param.id, &result).await;
+    Json(ResultResp::from(result))
+}
@@ -0,0 +1,221 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::time::{sleep, timeout, Instant};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// 重启等待时间上限
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+/// 任务运行超过此时间后再次出错，重置重启等待时间
+const STABLE_TIME: Duration = Duration::from_secs(60);
+
+#[derive(Copy, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TaskStatus {
+    Running,
+    /// 任务 panic，等待重启
+    Restarting,
+    /// 任务正常结束
+    Finished,
+    /// 服务关闭，任务已停止
+    Stopped,
+}
+
+/// 后台任务状态
+#[derive(Clone, Serialize)]
+pub(crate) struct TaskInfo {
+    name: &'static str,
+    status: TaskStatus,
+    /// 因 panic 重启的次数
+    restarts: u32,
+    /// 最后一次 panic 信息
+    last_error: Option<String>,
+    /// 最后一次启动时间（unix 时间戳，秒）
+    started_at: u64,
+}
+
+/// 后台任务管理，任务 panic 时按退避时间重启，服务关闭时统一取消并等待任务结束
+pub(crate) struct Supervisor;
+
+impl Supervisor {
+    fn inner(&self) -> MutexGuard<'static, BTreeMap<&'static str, TaskInfo>> {
+        static TASKS: OnceLock<Mutex<BTreeMap<&'static str, TaskInfo>>> = OnceLock::new();
+        TASKS.get_or_init(Default::default).lock().unwrap()
+    }
+
+    fn token(&self) -> &'static CancellationToken {
+        static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+        TOKEN.get_or_init(CancellationToken::new)
+    }
+
+    fn tracker(&self) -> &'static TaskTracker {
+        static TRACKER: OnceLock<TaskTracker> = OnceLock::new();
+        TRACKER.get_or_init(TaskTracker::new)
+    }
+
+    /// 启动命名的后台任务，任务需要在 token 取消后尽快结束
+    pub(crate) fn spawn<F, Fut>(&self, name: &'static str, job: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let token = self.token().clone();
+        self.tracker().spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            loop {
+                Supervisor.update(name, |it| {
+                    it.status = TaskStatus::Running;
+                    it.started_at = now();
+                });
+                let started = Instant::now();
+                let error = match tokio::spawn(job(token.clone())).await {
+                    Ok(_) => break,
+                    Err(e) if e.is_panic() => panic_message(e.into_panic()),
+                    Err(_) => break,
+                };
+
+                log::error!("background task `{name}` panicked: {error}");
+                Supervisor.update(name, |it| {
+                    it.status = TaskStatus::Restarting;
+                    it.restarts += 1;
+                    it.last_error = Some(error);
+                });
+                if started.elapsed() > STABLE_TIME {
+                    backoff = Duration::from_secs(1);
+                }
+                tokio::select! {
+                    _ = sleep(backoff) => {}
+                    _ = token.cancelled() => break,
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+            let status = match token.is_cancelled() {
+                true => TaskStatus::Stopped,
+                false => TaskStatus::Finished,
+            };
+            Supervisor.update(name, |it| it.status = status);
+        });
+    }
+
+    fn update(&self, name: &'static str, f: impl FnOnce(&mut TaskInfo)) {
+        let mut inner = self.inner();
+        let info = inner.entry(name).or_insert_with(|| TaskInfo {
+            name,
+            status: TaskStatus::Running,
+            restarts: 0,
+            last_error: None,
+            started_at: now(),
+        });
+        f(info);
+    }
+
+    pub(crate) fn tasks(&self) -> Vec<TaskInfo> {
+        self.inner().values().cloned().collect()
+    }
+
+    /// 是否有任务 panic 后等待重启
+    pub(crate) fn has_restarting(&self) -> bool {
+        let inner = self.inner();
+        inner
+            .values()
+            .any(|it| matches!(it.status, TaskStatus::Restarting))
+    }
+
+    /// 取消所有任务，并在超时时间内等待任务结束
+    pub(crate) async fn shutdown(&self, wait: Duration) {
+        self.token().cancel();
+        self.tracker().close();
+        if timeout(wait, self.tracker().wait()).await.is_err() {
+            log::warn!(
+                "background tasks not finished in {}s, force exit",
+                wait.as_secs()
+            );
+        }
+    }
+}
+
+fn now() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH);
+    now.map_or(0, |it| it.as_secs())
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(it) => *it,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(it) => it.to_string(),
+            Err(_) => "unknown panic".into(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn task(name: &str) -> Option<TaskInfo> {
+        Supervisor.tasks().into_iter().find(|it| it.name == name)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_restart_backoff() {
+        static CALLS: AtomicU32 = AtomicU32::new(0);
+        let started = Instant::now();
+        Supervisor.spawn("test_panic", |_| async {
+            if CALLS.fetch_add(1, Ordering::SeqCst) < 3 {
+                panic!("boom");
+            }
+        });
+        while !task("test_panic").is_some_and(|it| matches!(it.status, TaskStatus::Finished)) {
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        let info = task("test_panic").unwrap();
+        assert_eq!(CALLS.load(Ordering::SeqCst), 4);
+        assert_eq!(info.restarts, 3);
+        assert_eq!(info.last_error.as_deref(), Some("boom"));
+        // 依次等待 1s、2s、4s 后重启
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_secs(7), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(8), "{elapsed:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backoff_reset() {
+        static CALLS: AtomicU32 = AtomicU32::new(0);
+        let started = Instant::now();
+        Supervisor.spawn("test_stable", |_| async {
+            match CALLS.fetch_add(1, Ordering::SeqCst) {
+                0 => panic!("boom"),
+                1 => {
+                    sleep(STABLE_TIME + Duration::from_secs(1)).await;
+                    panic!("boom");
+                }
+                _ => {}
+            }
+        });
+        while !task("test_stable").is_some_and(|it| matches!(it.status, TaskStatus::Finished)) {
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        assert_eq!(task("test_stable").unwrap().restarts, 2);
+        // 运行超过 STABLE_TIME 后出错，等待时间重置为 1s
+        let elapsed = started.elapsed() - STABLE_TIME - Duration::from_secs(1);
+        assert!(elapsed >= Duration::from_secs(2), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(3), "{elapsed:?}");
+    }
+
+    #[test]
+    fn test_panic_message() {
+        assert_eq!(panic_message(Box::new("boom")), "boom");
+        assert_eq!(panic_message(Box::new(String::from("boom"))), "boom");
+        assert_eq!(panic_message(Box::new(1)), "unknown panic");
+    }
+}
@@ -1,4 +1,6 @@
-use reqwest::Client;
+use anyhow::Result;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 pub mod search;
@@ -29,3 +31,24 @@ impl From<Client> for Tmdb {
         Self(value)
     }
 }
+
+impl Tmdb {
+    /// 测试 tmdb api 是否可以访问，同时校验 api key
+    pub async fn connect_test(&self) -> Result<()> {
+        let req = self.0.get("https://api.themoviedb.org/3/configuration");
+        send(req.query(&[("api_key", TMDB_API)])).await?;
+        Ok(())
+    }
+}
+
+/// 发送请求，请求 URL 中包含 api key，错误信息中需要去掉 URL
+async fn send(req: RequestBuilder) -> reqwest::Result<Response> {
+    let resp = req.send().await.map_err(reqwest::Error::without_url)?;
+    resp.error_for_status().map_err(reqwest::Error::without_url)
+}
+
+/// 发送请求并解析 json 响应
+async fn send_json<T: DeserializeOwned>(req: RequestBuilder) -> reqwest::Result<T> {
+    let resp = send(req).await?;
+    resp.json().await.map_err(reqwest::Error::without_url)
+}
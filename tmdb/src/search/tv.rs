@@ -2,7 +2,7 @@ use anyhow::Result;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::{Language, Tmdb, TMDB_API};
+use crate::{send_json, Language, Tmdb, TMDB_API};
 
 #[derive(Serialize, Default, Debug)]
 struct Param<'a> {
@@ -67,8 +67,7 @@ impl<'a> SearchTv<'a> {
 
     pub async fn execute(self) -> Result<Resp> {
         let req = self.client.get("https://api.themoviedb.org/3/search/tv");
-        let resp = req.query(&self.param).send().await?;
-        let mut resp: Resp = resp.error_for_status()?.json().await?;
+        let mut resp: Resp = send_json(req.query(&self.param)).await?;
         resp.results = resp
             .results
             .into_iter()
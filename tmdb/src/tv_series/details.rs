@@ -2,7 +2,7 @@ use anyhow::Result;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::{Language, Tmdb, TMDB_API};
+use crate::{send_json, Language, Tmdb, TMDB_API};
 
 #[derive(Serialize, Default, Debug)]
 struct Param<'a> {
@@ -52,8 +52,7 @@ impl<'a> TvSeriesDetail<'a> {
     pub async fn execute(self) -> Result<Resp> {
         let rul = format!("https://api.themoviedb.org/3/tv/{}", self.series_id);
         let req = self.client.get(rul);
-        let resp = req.query(&self.param).send().await?;
-        Ok(resp.error_for_status()?.json().await?)
+        Ok(send_json(req.query(&self.param)).await?)
     }
 }
 